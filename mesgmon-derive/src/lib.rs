@@ -0,0 +1,169 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Type};
+
+struct Column {
+    ident: Ident,
+    ty: Type,
+    /// Type the value is stored as, when it differs from the field type.
+    sql: Option<Type>,
+    key: bool,
+    generated: bool,
+}
+
+/// Derives `mesgmon::infrastructure::Table` for a struct with named fields.
+///
+/// The struct takes `#[table(name = "...", history = "...")]`, with
+/// `entity = "Type"` the struct is the row of another type with the same
+/// fields and the mapping is implemented for that type instead. Fields map to
+/// the columns of the same name and take `#[column(...)]` with `key` for the
+/// primary key (the `id` field by default), `generated` for columns filled in
+/// by the database and `sql = "Type"` for values stored as another type
+/// through `From` and `TryFrom`.
+#[proc_macro_derive(Table, attributes(table, column))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let mut name = None;
+    let mut history = None;
+    let mut entity = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("table"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("history") {
+                history = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("entity") {
+                entity = Some(meta.value()?.parse::<LitStr>()?.parse::<Type>()?);
+            } else {
+                return Err(meta.error("expected `name`, `history` or `entity`"));
+            }
+            Ok(())
+        })?;
+    }
+    let name = name.ok_or_else(|| Error::new_spanned(input, "missing #[table(name = \"...\")]"))?;
+    let history =
+        history.ok_or_else(|| Error::new_spanned(input, "missing #[table(history = \"...\")]"))?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "Table can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(input, "Table requires named fields"));
+    };
+    let mut columns = Vec::with_capacity(fields.named.len());
+    for field in &fields.named {
+        let ident = field.ident.clone().unwrap();
+        let mut column = Column {
+            key: ident == "id",
+            ident,
+            ty: field.ty.clone(),
+            sql: None,
+            generated: false,
+        };
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("column"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    column.key = true;
+                } else if meta.path.is_ident("generated") {
+                    column.generated = true;
+                } else if meta.path.is_ident("sql") {
+                    column.sql = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `key`, `generated` or `sql`"));
+                }
+                Ok(())
+            })?;
+        }
+        columns.push(column);
+    }
+    // The key goes first, the generated columns are never written.
+    columns.sort_by_key(|column| (!column.key, column.generated));
+    if columns.iter().filter(|column| column.key).count() != 1 {
+        return Err(Error::new_spanned(
+            input,
+            "Table requires exactly one key column",
+        ));
+    }
+
+    let written: Vec<_> = columns.iter().filter(|column| !column.generated).collect();
+    let written_names = written.iter().map(|column| column.ident.to_string());
+    let generated_names = columns
+        .iter()
+        .filter(|column| column.generated)
+        .map(|column| column.ident.to_string());
+    let params = written.iter().map(|column| {
+        let ident = &column.ident;
+        match &column.sql {
+            Some(sql) => quote! { Box::new(<#sql>::from(self.#ident.clone())) },
+            None => quote! { Box::new(self.#ident.clone()) },
+        }
+    });
+    let fields = columns.iter().map(|column| {
+        let ident = &column.ident;
+        let name = ident.to_string();
+        let ty = &column.ty;
+        match &column.sql {
+            Some(sql) => quote! {
+                #ident: <#ty>::try_from(row.try_get::<_, #sql>(#name)?)?
+            },
+            None => quote! { #ident: row.try_get(#name)? },
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (target, from_row) = match &entity {
+        Some(entity) => {
+            let idents: Vec<_> = columns.iter().map(|column| &column.ident).collect();
+            (
+                quote! { #entity },
+                quote! {
+                    let row = #ident #ty_generics { #(#fields),* };
+                    Ok(#entity { #(#idents: row.#idents),* })
+                },
+            )
+        }
+        None => (
+            quote! { #ident #ty_generics },
+            quote! { Ok(#ident { #(#fields),* }) },
+        ),
+    };
+    Ok(quote! {
+        impl #impl_generics ::mesgmon::infrastructure::Table for #target #where_clause {
+            const NAME: &'static str = #name;
+            const HISTORY: &'static str = #history;
+            const COLUMNS: &'static [&'static str] = &[#(#written_names),*];
+            const GENERATED: &'static [&'static str] = &[#(#generated_names),*];
+
+            fn to_params(
+                &self,
+            ) -> Vec<Box<dyn ::tokio_postgres::types::ToSql + Send + Sync>> {
+                vec![#(#params),*]
+            }
+
+            fn from_row(
+                row: &::tokio_postgres::Row,
+            ) -> Result<Self, Box<dyn ::std::error::Error + Send + Sync>> {
+                #from_row
+            }
+        }
+    })
+}
@@ -0,0 +1,157 @@
+use crate::application::{AppState, ResourceService};
+use crate::domain::interfaces::Resource;
+use crate::handlers::{admin, catalog, inventory, middleware, orders, prices, resource};
+use crate::runtime::Runtime;
+use axum::extract::Request;
+use axum::response::IntoResponse;
+use axum::routing::{get, post, put, Route};
+use axum::Router;
+use std::convert::Infallible;
+use std::error::Error;
+use std::sync::Arc;
+use tower_layer::Layer;
+use tower_service::Service;
+
+type Layering = Box<dyn FnOnce(Router) -> Router + Send>;
+
+/// Assembles the HTTP application from the services of an `AppState`, extra
+/// resources and any routes and middleware of the embedding application.
+///
+/// Any `Database` can be plugged in through `infrastructure::Repository` and
+/// `CrudService`, or a custom `Service` can be given for a resource.
+pub struct AppBuilder {
+    state: Arc<AppState>,
+    router: Router,
+    layers: Vec<Layering>,
+    admin: bool,
+}
+
+impl AppBuilder {
+    /// Serves users with their orders, products with their prices and stock,
+    /// the catalog routes, and the admin and metrics routes behind the admin
+    /// token from `state` unless `without_admin` is called.
+    pub fn new(state: Arc<AppState>) -> AppBuilder {
+        let router = Router::new()
+            .merge(resource::routes("/users", state.users.clone()))
+            .merge(resource::routes("/products", state.products.clone()));
+        AppBuilder {
+            state,
+            router,
+            layers: Vec::new(),
+            admin: true,
+        }
+    }
+
+    /// Builder over the services `runtime` wires from the environment.
+    pub async fn from_env(
+        runtime: &mut Runtime,
+    ) -> Result<AppBuilder, Box<dyn Error + Send + Sync>> {
+        Ok(AppBuilder::new(runtime.state().await?))
+    }
+
+    /// Leaves out the admin and metrics routes, for embedders that mount
+    /// `admin_routes` themselves behind their own access control.
+    pub fn without_admin(mut self) -> AppBuilder {
+        self.admin = false;
+        self
+    }
+
+    /// Serves the entities of `T` under `path` with `service`.
+    pub fn resource<T: Resource>(mut self, path: &str, service: ResourceService<T>) -> AppBuilder {
+        self.router = self.router.merge(resource::routes(path, service));
+        self
+    }
+
+    /// Adds routes of the embedding application.
+    pub fn merge(mut self, router: Router) -> AppBuilder {
+        self.router = self.router.merge(router);
+        self
+    }
+
+    /// Wraps all routes in `layer`. Layers added later run first, the
+    /// principal of the request is read after all of them.
+    pub fn layer<L>(mut self, layer: L) -> AppBuilder
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.layers
+            .push(Box::new(move |router| router.layer(layer)));
+        self
+    }
+
+    pub fn build(self) -> Router {
+        let idempotency = self.state.idempotency.clone();
+        let trusted_proxies = self.state.trusted_proxies.clone();
+        let mut app = self.router.merge(domain_routes(self.state.clone()));
+        if self.admin {
+            app = app.merge(admin_routes(self.state.clone()).route_layer(
+                axum::middleware::from_fn_with_state(
+                    self.state.admin_token.clone(),
+                    middleware::admin,
+                ),
+            ));
+        }
+        if let Some(store) = idempotency {
+            app = app.layer(axum::middleware::from_fn_with_state(
+                store,
+                middleware::idempotency,
+            ));
+        }
+        app = app.layer(axum::middleware::from_fn_with_state(
+            trusted_proxies,
+            middleware::principal,
+        ));
+        for layer in self.layers {
+            app = layer(app);
+        }
+        app
+    }
+}
+
+/// Routes of the read models, prices, stock and orders.
+fn domain_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/catalog/products", get(catalog::products))
+        .route("/catalog/products/:id", get(catalog::product))
+        .route("/catalog/email-domains", get(catalog::email_domains))
+        .route(
+            "/products/:id/prices",
+            get(prices::history).post(prices::schedule),
+        )
+        .route(
+            "/products/:id/stock",
+            get(inventory::stock).put(inventory::set_stock),
+        )
+        .route("/products/:id/reservations", post(inventory::reserve))
+        .route("/reservations/:id", get(inventory::reservation))
+        .route("/reservations/:id/confirm", post(inventory::confirm))
+        .route("/reservations/:id/release", post(inventory::release))
+        .route("/orders", post(orders::create))
+        .route("/orders/:id", get(orders::get))
+        .route("/orders/:id/status", put(orders::set_status))
+        .route("/users/:id/orders", get(orders::for_user))
+        .with_state(state)
+}
+
+/// Operator and metrics routes, without any access control. `AppBuilder`
+/// serves them only to requests with the admin token of the state.
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/admin/publisher", get(admin::publisher))
+        .route("/admin/cdc", get(admin::cdc))
+        .route("/admin/kafka", get(admin::kafka))
+        .route("/admin/lag", get(admin::lag))
+        .route("/admin/projections", get(admin::projections))
+        .route("/admin/projections/:name/rebuild", post(admin::rebuild))
+        .route("/admin/topics", get(admin::topics))
+        .route(
+            "/admin/topics/:topic/partitions/:partition/messages",
+            get(admin::messages),
+        )
+        .route("/metrics", get(admin::metrics))
+        .with_state(state)
+}
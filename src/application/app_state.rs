@@ -1,28 +1,80 @@
-use crate::domain::dto::{Credentials, Description};
-use crate::domain::interfaces::{Identifiable, MessageBroker, Repository, Service};
+use crate::application::{CrudService, InventoryService, OrderService, PriceService};
+use crate::domain::interfaces::{Identifiable, MessageBroker, Repository, Resource, Service};
 use crate::domain::models::{Product, User};
+use crate::infrastructure::{
+    ChangeCapture, IdempotencyStore, KafkaMetrics, LagMonitor, Projector, QueuedBroker, ReadModel,
+    TopicInspector,
+};
 use std::error;
+use std::net::IpAddr;
 use std::sync::Arc;
 use uuid::Uuid;
 
 type Error = Box<dyn error::Error + Send + Sync>;
-type Id = dyn Identifiable + Sync + Send;
-type Broker = Arc<dyn MessageBroker<Id, Error = Error> + Sync + Send>;
+type Broker = Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Error> + Send + Sync>;
+type Repo<T> = Arc<dyn Repository<T, Error = Error, Id = Uuid> + Send + Sync>;
+pub type ResourceService<T> =
+    Arc<dyn Service<T, <T as Resource>::Dto, Error = Error> + Send + Sync>;
 
-type UserRepo = Arc<dyn Repository<User, Error = Error, Id = Uuid> + Sync + Send>;
-type UserService = dyn Service<User, Credentials, Error = Error, MessageBroker = Broker, Repository = UserRepo>
-    + Send
-    + Sync;
+/// The services the routes are served by. The admin components are optional
+/// and their routes answer 404 when one is missing.
+pub struct AppState {
+    pub users: ResourceService<User>,
+    pub products: ResourceService<Product>,
+    pub publisher: Option<Arc<QueuedBroker>>,
+    pub kafka_metrics: Option<Arc<KafkaMetrics>>,
+    pub inspector: Option<Arc<TopicInspector>>,
+    pub lag_monitor: Option<Arc<LagMonitor>>,
+    pub change_capture: Option<Arc<ChangeCapture>>,
+    pub projector: Option<Arc<Projector>>,
+    pub read_model: Option<Arc<ReadModel>>,
+    /// Stores the responses of requests with an `Idempotency-Key`, without it
+    /// the header is ignored.
+    pub idempotency: Option<Arc<IdempotencyStore>>,
+    /// Keeps the price timelines of the products.
+    pub prices: Option<Arc<PriceService>>,
+    /// Tracks the stock of the products and the reservations against it.
+    pub inventory: Option<Arc<InventoryService>>,
+    /// Places the orders of the users and moves them through their statuses.
+    pub orders: Option<Arc<OrderService>>,
+    /// Bearer token the admin and metrics routes require, without it they
+    /// are closed.
+    pub admin_token: Option<Arc<str>>,
+    /// Proxies whose `X-Principal` header names the caller. The peer address
+    /// is only known when the router is served with `ConnectInfo`.
+    pub trusted_proxies: Arc<[IpAddr]>,
+}
 
-type ProductRepo = Arc<dyn Repository<Product, Error = Error, Id = Uuid> + Sync + Send>;
-type ProductService = dyn Service<Product, Description, Error = Error, MessageBroker = Broker, Repository = ProductRepo>
-    + Send
-    + Sync;
+impl AppState {
+    pub fn new(users: ResourceService<User>, products: ResourceService<Product>) -> AppState {
+        AppState {
+            users,
+            products,
+            publisher: None,
+            kafka_metrics: None,
+            inspector: None,
+            lag_monitor: None,
+            change_capture: None,
+            projector: None,
+            read_model: None,
+            idempotency: None,
+            prices: None,
+            inventory: None,
+            orders: None,
+            admin_token: None,
+            trusted_proxies: Arc::from([]),
+        }
+    }
 
-pub struct AppState {
-    pub broker: Broker,
-    pub user_repo: UserRepo,
-    pub product_repo: ProductRepo,
-    pub user_service: Arc<UserService>,
-    pub product_service: Arc<ProductService>,
+    /// State with the CRUD services over the given repositories.
+    pub fn with_repositories(
+        users: Repo<User>,
+        products: Repo<Product>,
+        broker: Broker,
+    ) -> AppState {
+        AppState::new(
+            Arc::new(CrudService::new("user-events", users, broker.clone())),
+            Arc::new(CrudService::new("product-events", products, broker)),
+        )
+    }
 }
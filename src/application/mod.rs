@@ -1,4 +1,8 @@
 mod app_state;
 mod services;
-pub use app_state::AppState;
-pub use services::{ProductService, UserService};
+pub use app_state::{AppState, ResourceService};
+pub use services::{
+    BatchTooLarge, CrudService, InventoryError, InventoryService, OrderError, OrderService,
+    PriceService, ProductService, PurgeService, ReplayOptions, ReplayService, DEFAULT_BATCH_LIMIT,
+    DEFAULT_MAX_RESERVATION_TTL,
+};
@@ -0,0 +1,629 @@
+use crate::domain::dto::{Batch, BatchMode, Operation, Patch, PatchError, Scan};
+use crate::domain::interfaces;
+use crate::domain::interfaces::{Identifiable, MessageBroker, Repository, Resource, Units};
+use crate::domain::models::{Change, Envelope, Version};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use uuid::Uuid;
+
+type Broker = Arc<
+    dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Box<dyn Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type Repo<T> =
+    Arc<dyn Repository<T, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>;
+type Outcomes<T> = Vec<Result<Change<T>, Box<dyn Error + Send + Sync>>>;
+type UnitsOf<T> = Arc<dyn Units<T, Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+/// Operations a batch may contain by default.
+pub const DEFAULT_BATCH_LIMIT: usize = 1000;
+
+#[derive(Debug)]
+pub struct BatchTooLarge {
+    pub limit: usize,
+}
+
+impl fmt::Display for BatchTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "batch exceeds the limit of {} operations", self.limit)
+    }
+}
+
+impl Error for BatchTooLarge {}
+
+/// Stores the entities of a resource and publishes an event to its topic for
+/// every change.
+pub struct CrudService<T> {
+    topic: String,
+    repo: Repo<T>,
+    broker: Broker,
+    units: Option<UnitsOf<T>>,
+    batch_limit: usize,
+    upsert: bool,
+}
+
+impl<T> CrudService<T> {
+    pub fn new(topic: &str, repo: Repo<T>, broker: Broker) -> CrudService<T> {
+        CrudService {
+            topic: topic.to_string(),
+            repo,
+            broker,
+            units: None,
+            batch_limit: DEFAULT_BATCH_LIMIT,
+            upsert: false,
+        }
+    }
+
+    /// Runs atomic batches in units of work of `units`, without them only
+    /// partial batches are accepted.
+    pub fn with_units(mut self, units: UnitsOf<T>) -> CrudService<T> {
+        self.units = Some(units);
+        self
+    }
+
+    pub fn with_batch_limit(mut self, batch_limit: usize) -> CrudService<T> {
+        self.batch_limit = batch_limit;
+        self
+    }
+
+    /// Lets `upsert` create the entities that do not exist.
+    pub fn with_upsert(mut self, upsert: bool) -> CrudService<T> {
+        self.upsert = upsert;
+        self
+    }
+}
+
+impl<T: Resource> CrudService<T> {
+    async fn update_in(
+        repo: &Repo<T>,
+        id: Uuid,
+        dto: T::Dto,
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let Some(mut item) = repo.get(id).await? else {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        };
+        item.apply(dto);
+        let Some(item) = repo.update(item).await? else {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        };
+        Ok(item)
+    }
+
+    async fn delete_in(repo: &Repo<T>, id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !repo.remove(id).await? {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        }
+        Ok(())
+    }
+
+    async fn apply_in(
+        repo: &Repo<T>,
+        operation: Operation<T::Dto>,
+    ) -> Result<Change<T>, Box<dyn Error + Send + Sync>> {
+        match operation {
+            Operation::Create { data } => Ok(Change::Created(
+                repo.add(T::create(Uuid::new_v4(), data)).await?,
+            )),
+            Operation::Update { id, data } => {
+                Ok(Change::Updated(Self::update_in(repo, id, data).await?))
+            }
+            Operation::Delete { id } => {
+                Self::delete_in(repo, id).await?;
+                Ok(Change::Deleted(id))
+            }
+        }
+    }
+
+    /// Applies every operation on its own and publishes the events of those
+    /// that succeeded together.
+    async fn partial(
+        &self,
+        operations: Vec<Operation<T::Dto>>,
+    ) -> Result<Outcomes<T>, Box<dyn Error + Send + Sync>> {
+        let mut results = Vec::with_capacity(operations.len());
+        for operation in operations {
+            results.push(Self::apply_in(&self.repo, operation).await);
+        }
+        let envelopes = results
+            .iter()
+            .flatten()
+            .map(|change| change.envelope(&self.topic))
+            .collect();
+        self.broker.publish_all(envelopes).await?;
+        Ok(results)
+    }
+
+    /// Applies the operations in one unit of work.
+    async fn atomic(
+        &self,
+        operations: Vec<Operation<T::Dto>>,
+    ) -> Result<Outcomes<T>, Box<dyn Error + Send + Sync>> {
+        let Some(units) = &self.units else {
+            Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "atomic batches are not supported by this storage, use the partial mode",
+            ))?
+        };
+        let unit = units.begin_unit().await?;
+        let results = self
+            .within(unit.repository(), unit.broker())
+            .apply_all(operations)
+            .await?;
+        if matches!(results.last(), Some(Err(_))) {
+            unit.rollback().await?;
+        } else {
+            unit.commit().await?;
+        }
+        Ok(results)
+    }
+
+    /// Applies the operations until one fails, consecutive creates with a
+    /// single insert, and publishes their events when all succeeded.
+    pub(crate) async fn apply_all(
+        &self,
+        operations: Vec<Operation<T::Dto>>,
+    ) -> Result<Outcomes<T>, Box<dyn Error + Send + Sync>> {
+        let mut results = Vec::with_capacity(operations.len());
+        let mut operations = operations.into_iter().peekable();
+        while let Some(operation) = operations.next() {
+            let result = match operation {
+                Operation::Create { data } => {
+                    let mut items = vec![T::create(Uuid::new_v4(), data)];
+                    while let Some(Operation::Create { data }) = operations
+                        .next_if(|operation| matches!(operation, Operation::Create { .. }))
+                    {
+                        items.push(T::create(Uuid::new_v4(), data));
+                    }
+                    match self.repo.add_all(items.clone()).await {
+                        Ok(items) => Ok(items.into_iter().map(Change::Created).collect()),
+                        // None of them is stored, adding them one by one
+                        // tells which fails.
+                        Err(_) if items.len() > 1 => {
+                            for item in items {
+                                match self.repo.add(item).await {
+                                    Ok(item) => results.push(Ok(Change::Created(item))),
+                                    Err(error) => {
+                                        results.push(Err(error));
+                                        return Ok(results);
+                                    }
+                                }
+                            }
+                            Ok(Vec::new())
+                        }
+                        Err(error) => Err(error),
+                    }
+                }
+                operation => Self::apply_in(&self.repo, operation)
+                    .await
+                    .map(|change| vec![change]),
+            };
+            match result {
+                Ok(changes) => results.extend(changes.into_iter().map(Ok)),
+                Err(error) => {
+                    results.push(Err(error));
+                    return Ok(results);
+                }
+            }
+        }
+        let envelopes = results
+            .iter()
+            .flatten()
+            .map(|change| change.envelope(&self.topic))
+            .collect();
+        self.broker.publish_all(envelopes).await?;
+        Ok(results)
+    }
+
+    /// The service over the repository and broker of a unit of work, with
+    /// the same topic and limits.
+    pub(crate) fn within(&self, repo: Repo<T>, broker: Broker) -> CrudService<T> {
+        CrudService {
+            topic: self.topic.clone(),
+            repo,
+            broker,
+            units: None,
+            batch_limit: self.batch_limit,
+            upsert: self.upsert,
+        }
+    }
+
+    pub(crate) fn check_batch(&self, batch: &Batch<T::Dto>) -> Result<(), BatchTooLarge> {
+        if batch.operations.len() > self.batch_limit {
+            return Err(BatchTooLarge {
+                limit: self.batch_limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: Resource> interfaces::Service<T, T::Dto> for CrudService<T> {
+    type Error = Box<dyn Error + Send + Sync>;
+
+    async fn create(&self, dto: T::Dto) -> Result<T, Self::Error> {
+        let item = self.repo.add(T::create(Uuid::new_v4(), dto)).await?;
+        self.broker.send(&self.topic, "create", &item).await?;
+        Ok(item)
+    }
+
+    async fn get(&self, id: Uuid, as_of: Option<DateTime<Utc>>) -> Result<Option<T>, Self::Error> {
+        match as_of {
+            Some(as_of) => self.repo.get_as_of(id, as_of).await,
+            None => self.repo.get(id).await,
+        }
+    }
+
+    async fn list(&self, scan: &Scan) -> Result<Vec<T>, Self::Error> {
+        self.repo.scan(scan).await
+    }
+
+    async fn history(&self, id: Uuid) -> Result<Vec<Version<T>>, Self::Error> {
+        self.repo.history(id).await
+    }
+
+    async fn update(&self, id: Uuid, dto: T::Dto) -> Result<T, Self::Error> {
+        let item = Self::update_in(&self.repo, id, dto).await?;
+        self.broker.send(&self.topic, "update", &item).await?;
+        Ok(item)
+    }
+
+    async fn upsert(&self, id: Uuid, dto: T::Dto) -> Result<Change<T>, Self::Error> {
+        if !self.upsert {
+            return Ok(Change::Updated(self.update(id, dto).await?));
+        }
+        let Some(change) = self.repo.upsert(T::create(id, dto)).await? else {
+            Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                "the entity is deleted, restore it first",
+            ))?
+        };
+        self.broker.publish(change.envelope(&self.topic)).await?;
+        Ok(change)
+    }
+
+    async fn patch(&self, id: Uuid, patch: Patch) -> Result<T, Self::Error> {
+        let Some(mut item) = self.repo.get(id).await? else {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        };
+        let mut document = serde_json::to_value(item.dto())?;
+        patch.apply(&mut document)?;
+        let dto: T::Dto = serde_json::from_value(document.clone())
+            .map_err(|error| PatchError::Invalid(error.to_string()))?;
+        let unknown = unknown_fields(&serde_json::to_value(&dto)?, &document);
+        if !unknown.is_empty() {
+            Err(PatchError::Invalid(format!(
+                "unknown fields {}",
+                unknown.join(", ")
+            )))?
+        }
+        let before = serde_json::to_value(&item)?;
+        item.apply(dto);
+        let changed = changed_fields(&before, &serde_json::to_value(&item)?);
+        if changed.is_empty() {
+            return Ok(item);
+        }
+        let Some(item) = self.repo.update_fields(item, &changed).await? else {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        };
+        let envelope = Envelope {
+            changed,
+            ..Envelope::new(&self.topic, "update", &item)
+        };
+        self.broker.publish(envelope).await?;
+        Ok(item)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), Self::Error> {
+        Self::delete_in(&self.repo, id).await?;
+        self.broker.send(&self.topic, "delete", &id).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<(), Self::Error> {
+        if !self.repo.restore(id).await? {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        }
+        self.broker.send(&self.topic, "restore", &id).await
+    }
+
+    async fn batch(&self, batch: Batch<T::Dto>) -> Result<Outcomes<T>, Self::Error> {
+        self.check_batch(&batch)?;
+        match batch.mode {
+            BatchMode::Atomic => self.atomic(batch.operations).await,
+            BatchMode::Partial => self.partial(batch.operations).await,
+        }
+    }
+}
+
+/// Names of the top level fields of `document` that `known` does not have.
+fn unknown_fields(known: &Value, document: &Value) -> Vec<String> {
+    let (Value::Object(known), Value::Object(document)) = (known, document) else {
+        return Vec::new();
+    };
+    document
+        .keys()
+        .filter(|name| !known.contains_key(name.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Names of the top level fields that differ between two serialized entities.
+fn changed_fields(before: &Value, after: &Value) -> Vec<String> {
+    let (Value::Object(before), Value::Object(after)) = (before, after) else {
+        return Vec::new();
+    };
+    after
+        .iter()
+        .filter(|(name, value)| before.get(name.as_str()) != Some(value))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::dto::{Credentials, Description, PatchOperation};
+    use crate::domain::interfaces::{Database, Service};
+    use crate::domain::models::{Product, User};
+    use crate::infrastructure::{PostgresUnitOfWork, Repository};
+    use crate::test_support::{is_not_found, postgres, usd, Recorder};
+    use serde_json::json;
+
+    fn credentials() -> Credentials {
+        Credentials {
+            name: "test".to_string(),
+            email: format!("{}@example.com", Uuid::new_v4()),
+        }
+    }
+
+    /// Checks that the service of `T` publishes its changes and nothing for
+    /// the items that do not exist.
+    async fn publish_only_changes<T: Resource>(topic: &str, repo: Repo<T>, dto: fn() -> T::Dto) {
+        let recorder = Arc::new(Recorder::default());
+        let service = CrudService::<T>::new(topic, repo, recorder.clone());
+        let missing = Uuid::new_v4();
+
+        let error = service.delete(missing).await.unwrap_err();
+        assert!(is_not_found(&error));
+        let error = service.update(missing, dto()).await.err().unwrap();
+        assert!(is_not_found(&error));
+        assert!(recorder.sent.lock().unwrap().is_empty());
+
+        let item = service.create(dto()).await.unwrap();
+        let id = item.id().parse().unwrap();
+        service.update(id, dto()).await.unwrap();
+        service.delete(id).await.unwrap();
+        let error = service.delete(id).await.unwrap_err();
+        assert!(is_not_found(&error));
+        let expected: Vec<_> = ["create", "update", "delete"]
+            .iter()
+            .map(|action| (topic.to_string(), action.to_string(), item.id()))
+            .collect();
+        assert_eq!(*recorder.sent.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn services_publish_only_changes() {
+        let postgres = postgres().await;
+        let users = Arc::new(Repository::<User> {
+            storage: postgres.clone(),
+        });
+        publish_only_changes("user-events", users, credentials).await;
+        let products = Arc::new(Repository::<Product> { storage: postgres });
+        let description = || Description {
+            name: "test".to_string(),
+            price: usd(100),
+        };
+        publish_only_changes("product-events", products, description).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn product_batches_publish_what_they_apply() {
+        let postgres = postgres().await;
+        let repo = Arc::new(Repository::<Product> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let units = Arc::new(PostgresUnitOfWork::new(postgres.clone(), recorder.clone()));
+        let service =
+            CrudService::<Product>::new("product-events", repo, recorder.clone()).with_units(units);
+        let create = || Operation::Create {
+            data: Description {
+                name: "test".to_string(),
+                price: usd(100),
+            },
+        };
+        let missing = Uuid::new_v4();
+
+        let results = service
+            .batch(Batch {
+                mode: BatchMode::Atomic,
+                operations: vec![create(), create(), Operation::Delete { id: missing }],
+            })
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(is_not_found(results[2].as_ref().unwrap_err()));
+        for result in &results[..2] {
+            let id = result.as_ref().unwrap().id().parse().unwrap();
+            assert!(Database::<Product>::get(&*postgres, id)
+                .await
+                .unwrap()
+                .is_none());
+        }
+        assert!(recorder.sent.lock().unwrap().is_empty());
+
+        let results = service
+            .batch(Batch {
+                mode: BatchMode::Partial,
+                operations: vec![create(), Operation::Delete { id: missing }, create()],
+            })
+            .await
+            .unwrap();
+        assert!(is_not_found(results[1].as_ref().unwrap_err()));
+        let expected: Vec<_> = [&results[0], &results[2]]
+            .iter()
+            .map(|result| {
+                let id = result.as_ref().unwrap().id();
+                ("product-events".to_string(), "create".to_string(), id)
+            })
+            .collect();
+        assert_eq!(*recorder.sent.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn batches_report_the_create_that_fails() {
+        let postgres = postgres().await;
+        let repo = Arc::new(Repository::<User> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let units = Arc::new(PostgresUnitOfWork::new(postgres.clone(), recorder.clone()));
+        let service =
+            CrudService::<User>::new("user-events", repo, recorder.clone()).with_units(units);
+        let taken = service.create(credentials()).await.unwrap();
+        let mut operations: Vec<_> = (0..5)
+            .map(|_| Operation::Create {
+                data: credentials(),
+            })
+            .collect();
+        operations[2] = Operation::Create {
+            data: Credentials {
+                name: "test".to_string(),
+                email: taken.email.clone(),
+            },
+        };
+
+        let results = service
+            .batch(Batch {
+                mode: BatchMode::Atomic,
+                operations,
+            })
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[..2].iter().all(Result::is_ok));
+        let error = results[2].as_ref().unwrap_err();
+        let error = error.downcast_ref::<tokio_postgres::Error>().unwrap();
+        assert_eq!(
+            error.code(),
+            Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION)
+        );
+        for result in &results[..2] {
+            let id = result.as_ref().unwrap().id().parse().unwrap();
+            assert!(Database::<User>::get(&*postgres, id)
+                .await
+                .unwrap()
+                .is_none());
+        }
+        assert_eq!(recorder.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn user_patch_stores_and_announces_changed_fields() {
+        let postgres = postgres().await;
+        let repo = Arc::new(Repository::<User> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let service = CrudService::<User>::new("user-events", repo, recorder.clone());
+        let user = service
+            .create(Credentials {
+                name: "test".to_string(),
+                email: format!("{}@example.com", Uuid::new_v4()),
+            })
+            .await
+            .unwrap();
+        let test = |value: &str| {
+            Patch::Json(vec![PatchOperation::Test {
+                path: "/name".to_string(),
+                value: json!(value),
+            }])
+        };
+
+        let patch = Patch::Merge(json!({"name": "patched"}));
+        let patched = service.patch(user.id, patch).await.unwrap();
+        assert_eq!(
+            (patched.name.as_str(), &patched.email),
+            ("patched", &user.email)
+        );
+        service.patch(user.id, test("patched")).await.unwrap();
+        let error = service.patch(user.id, test("test")).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PatchError>(),
+            Some(PatchError::TestFailed(_))
+        ));
+        let patch = Patch::Merge(json!({"role": "admin"}));
+        let error = service.patch(user.id, patch).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PatchError>(),
+            Some(PatchError::Invalid(_))
+        ));
+
+        assert_eq!(recorder.sent.lock().unwrap().len(), 2);
+        assert_eq!(
+            *recorder.changed.lock().unwrap(),
+            vec![vec!["name".to_string()]]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn product_upserts_create_missing_ids_when_enabled() {
+        let postgres = postgres().await;
+        let repo = Arc::new(Repository::<Product> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let description = |price| Description {
+            name: "test".to_string(),
+            price: usd(price),
+        };
+        let id = Uuid::new_v4();
+
+        let strict = CrudService::<Product>::new("product-events", repo.clone(), recorder.clone());
+        let error = strict.upsert(id, description(100)).await.unwrap_err();
+        assert!(is_not_found(&error));
+
+        let service =
+            CrudService::<Product>::new("product-events", repo, recorder.clone()).with_upsert(true);
+        let Change::Created(created) = service.upsert(id, description(100)).await.unwrap() else {
+            panic!("expected the product to be created");
+        };
+        assert_eq!(created.id, id);
+        let Change::Updated(updated) = service.upsert(id, description(200)).await.unwrap() else {
+            panic!("expected the product to be replaced");
+        };
+        assert_eq!(updated.price, usd(200));
+        assert_eq!(updated.created_at, created.created_at);
+
+        service.delete(id).await.unwrap();
+        let error = service.upsert(id, description(300)).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<io::Error>(),
+            Some(error) if error.kind() == ErrorKind::AlreadyExists
+        ));
+        let expected: Vec<_> = ["create", "update", "delete"]
+            .iter()
+            .map(|action| {
+                (
+                    "product-events".to_string(),
+                    action.to_string(),
+                    id.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(*recorder.sent.lock().unwrap(), expected);
+    }
+}
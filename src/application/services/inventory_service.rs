@@ -0,0 +1,320 @@
+use crate::domain::dto::{ReservationRequest, StockLevel};
+use crate::domain::interfaces::{Identifiable, Inventory, MessageBroker, Repository};
+use crate::domain::models::{Product, Reservation, ReservationStatus, Stock};
+use chrono::Utc;
+use log::{error, info};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+type Broker = Arc<
+    dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Box<dyn Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type Products =
+    Arc<dyn Repository<Product, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>;
+type Stocks = Arc<dyn Inventory<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+#[derive(Debug)]
+pub enum InventoryError {
+    /// Fewer units are available than requested.
+    InsufficientStock { available: i64 },
+    /// The units on hand would be fewer than those reserved.
+    BelowReserved { reserved: i64 },
+    /// The reservation is no longer pending.
+    Closed(ReservationStatus),
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::InsufficientStock { available } => {
+                write!(f, "insufficient stock, available: {available}")
+            }
+            InventoryError::BelowReserved { reserved } => {
+                write!(f, "stock cannot be below the reserved {reserved}")
+            }
+            InventoryError::Closed(status) => write!(f, "reservation is {status}"),
+        }
+    }
+}
+
+impl Error for InventoryError {}
+
+/// Tracks the stock of the products and the reservations against it, and
+/// publishes a `low-stock` event when the available units of a product fall
+/// to its threshold.
+pub struct InventoryService {
+    topic: String,
+    products: Products,
+    inventory: Stocks,
+    broker: Broker,
+    reservation_ttl: Duration,
+    max_reservation_ttl: Duration,
+    batch_size: usize,
+}
+
+/// Longest hold a reservation may ask for by default.
+pub const DEFAULT_MAX_RESERVATION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl InventoryService {
+    pub fn new(
+        topic: &str,
+        products: Products,
+        inventory: Stocks,
+        broker: Broker,
+        reservation_ttl: Duration,
+        batch_size: usize,
+    ) -> InventoryService {
+        InventoryService {
+            topic: topic.to_string(),
+            products,
+            inventory,
+            broker,
+            reservation_ttl,
+            max_reservation_ttl: DEFAULT_MAX_RESERVATION_TTL.max(reservation_ttl),
+            batch_size,
+        }
+    }
+
+    /// Holds units for at most `max` whatever a reservation asks for.
+    pub fn with_max_reservation_ttl(mut self, max: Duration) -> InventoryService {
+        self.max_reservation_ttl = max;
+        self
+    }
+
+    async fn product_exists(&self, id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.products.get(id).await?.is_none() {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        }
+        Ok(())
+    }
+
+    /// Publishes `low-stock` when the stock is low and was not before.
+    async fn announce(
+        &self,
+        before: i64,
+        stock: &Stock,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if stock.is_low() && before > stock.low_stock_threshold {
+            self.broker.send(&self.topic, "low-stock", stock).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn stock(&self, id: Uuid) -> Result<Stock, Box<dyn Error + Send + Sync>> {
+        self.product_exists(id).await?;
+        Ok(self
+            .inventory
+            .stock(id)
+            .await?
+            .unwrap_or_else(|| Stock::empty(id)))
+    }
+
+    pub async fn set_stock(
+        &self,
+        id: Uuid,
+        level: StockLevel,
+    ) -> Result<Stock, Box<dyn Error + Send + Sync>> {
+        if level.on_hand < 0 || level.low_stock_threshold < 0 {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "stock levels must not be negative",
+            ))?
+        }
+        let before = self.stock(id).await?;
+        let Some(stock) = self
+            .inventory
+            .set_stock(id, level.on_hand, level.low_stock_threshold)
+            .await?
+        else {
+            let reserved = self.stock(id).await?.reserved;
+            Err(InventoryError::BelowReserved { reserved })?
+        };
+        self.announce(before.available, &stock).await?;
+        Ok(stock)
+    }
+
+    pub async fn reservation(&self, id: Uuid) -> Result<Reservation, Box<dyn Error + Send + Sync>> {
+        match self.inventory.reservation(id).await? {
+            Some(reservation) => Ok(reservation),
+            None => Err(io::Error::from(ErrorKind::NotFound))?,
+        }
+    }
+
+    pub async fn reserve(
+        &self,
+        id: Uuid,
+        request: ReservationRequest,
+    ) -> Result<Reservation, Box<dyn Error + Send + Sync>> {
+        if request.quantity <= 0 {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "quantity must be positive",
+            ))?
+        }
+        self.product_exists(id).await?;
+        let ttl = request
+            .ttl_secs
+            .map_or(self.reservation_ttl, Duration::from_secs)
+            .min(self.max_reservation_ttl);
+        let expires_at = Utc::now() + chrono::Duration::from_std(ttl)?;
+        let Some((reservation, stock)) = self
+            .inventory
+            .reserve(id, request.quantity, expires_at)
+            .await?
+        else {
+            let available = self.stock(id).await?.available;
+            Err(InventoryError::InsufficientStock { available })?
+        };
+        self.announce(stock.available + request.quantity, &stock)
+            .await?;
+        Ok(reservation)
+    }
+
+    pub async fn confirm(&self, id: Uuid) -> Result<Reservation, Box<dyn Error + Send + Sync>> {
+        match self.inventory.confirm(id).await? {
+            Some((reservation, _)) => Ok(reservation),
+            None => Err(self.closed(id).await?)?,
+        }
+    }
+
+    pub async fn release(&self, id: Uuid) -> Result<Reservation, Box<dyn Error + Send + Sync>> {
+        match self.inventory.release(id).await? {
+            Some((reservation, _)) => Ok(reservation),
+            None => Err(self.closed(id).await?)?,
+        }
+    }
+
+    /// Why a reservation could not be closed.
+    async fn closed(&self, id: Uuid) -> Result<InventoryError, Box<dyn Error + Send + Sync>> {
+        let reservation = self.reservation(id).await?;
+        match reservation.status {
+            // Confirmed after it expired, before the background task ran.
+            ReservationStatus::Pending => Ok(InventoryError::Closed(ReservationStatus::Expired)),
+            status => Ok(InventoryError::Closed(status)),
+        }
+    }
+
+    /// Returns the units of expired reservations to the stock every
+    /// `interval`.
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match service.expire().await {
+                    Ok(0) => {}
+                    Ok(expired) => info!("Released {} expired reservations", expired),
+                    Err(err) => error!("Release of expired reservations failed: {}", err),
+                }
+            }
+        });
+    }
+
+    pub async fn expire(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut expired = 0;
+        loop {
+            let reservations = self.inventory.expire(Utc::now(), self.batch_size).await?;
+            expired += reservations.len();
+            if reservations.len() < self.batch_size {
+                return Ok(expired);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::dto::StockLevel;
+    use crate::domain::interfaces::Database;
+    use crate::infrastructure::{PostgresInventory, Repository};
+    use crate::test_support::{postgres, product, Recorder};
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn reservations_never_oversell_and_return_their_units() {
+        let postgres = postgres().await;
+        let repo = Arc::new(Repository::<Product> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let inventory = Arc::new(InventoryService::new(
+            "inventory-events",
+            repo,
+            Arc::new(PostgresInventory::new(postgres.pool())),
+            recorder.clone(),
+            Duration::from_secs(60),
+            10,
+        ));
+        let product = postgres.add(product()).await.unwrap();
+        let level = StockLevel {
+            on_hand: 5,
+            low_stock_threshold: 1,
+        };
+        inventory.set_stock(product.id, level).await.unwrap();
+
+        let reserve = |ttl_secs| {
+            let inventory = inventory.clone();
+            tokio::spawn(async move {
+                let request = ReservationRequest {
+                    quantity: 1,
+                    ttl_secs,
+                };
+                inventory.reserve(product.id, request).await
+            })
+        };
+        let attempts: Vec<_> = (0..8).map(|_| reserve(None)).collect();
+        let mut reservations = Vec::new();
+        for attempt in attempts {
+            match attempt.await.unwrap() {
+                Ok(reservation) => reservations.push(reservation),
+                Err(error) => assert!(error.downcast_ref::<InventoryError>().is_some()),
+            }
+        }
+        assert_eq!(reservations.len(), 5);
+        let stock = inventory.stock(product.id).await.unwrap();
+        assert_eq!((stock.on_hand, stock.reserved, stock.available), (5, 5, 0));
+        let low: Vec<_> = recorder
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, action, id)| action == "low-stock" && *id == product.id())
+            .cloned()
+            .collect();
+        assert_eq!(low.len(), 1);
+
+        let confirmed = inventory.confirm(reservations[0].id).await.unwrap();
+        assert_eq!(confirmed.status, ReservationStatus::Confirmed);
+        inventory.release(reservations[1].id).await.unwrap();
+        let error = inventory.release(reservations[0].id).await.unwrap_err();
+        assert!(error.downcast_ref::<InventoryError>().is_some());
+        let stock = inventory.stock(product.id).await.unwrap();
+        assert_eq!((stock.on_hand, stock.reserved, stock.available), (4, 3, 1));
+        let below = StockLevel {
+            on_hand: 2,
+            low_stock_threshold: 1,
+        };
+        assert!(inventory.set_stock(product.id, below).await.is_err());
+
+        let expiring = reserve(Some(0)).await.unwrap().unwrap();
+        assert!(inventory.expire().await.unwrap() >= 1);
+        let expired = inventory.reservation(expiring.id).await.unwrap();
+        assert_eq!(expired.status, ReservationStatus::Expired);
+        let stock = inventory.stock(product.id).await.unwrap();
+        assert_eq!((stock.on_hand, stock.reserved, stock.available), (4, 3, 1));
+
+        // Holds are cut to the maximum however long they are asked for.
+        let held = reserve(Some(u64::MAX)).await.unwrap().unwrap();
+        assert!(held.expires_at <= Utc::now() + chrono::Duration::days(1));
+        inventory.release(held.id).await.unwrap();
+    }
+}
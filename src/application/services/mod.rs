@@ -1,5 +1,15 @@
-mod user_service;
+mod crud_service;
+mod inventory_service;
+mod order_service;
+mod price_service;
 mod product_service;
+mod purge_service;
+mod replay_service;
 
-pub use user_service::UserService;
-pub use product_service::ProductService;
\ No newline at end of file
+pub use crud_service::{BatchTooLarge, CrudService, DEFAULT_BATCH_LIMIT};
+pub use inventory_service::{InventoryError, InventoryService, DEFAULT_MAX_RESERVATION_TTL};
+pub use order_service::{OrderError, OrderService};
+pub use price_service::PriceService;
+pub use product_service::ProductService;
+pub use purge_service::PurgeService;
+pub use replay_service::{ReplayOptions, ReplayService};
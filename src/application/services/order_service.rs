@@ -0,0 +1,269 @@
+use crate::domain::dto::NewOrder;
+use crate::domain::interfaces::{Identifiable, MessageBroker, OrderRepository, Repository};
+use crate::domain::models::{Money, Order, OrderLine, OrderStatus, Product, User};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use uuid::Uuid;
+
+type Broker = Arc<
+    dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Box<dyn Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type Orders = Arc<dyn OrderRepository<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+type Users =
+    Arc<dyn Repository<User, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>;
+type Products =
+    Arc<dyn Repository<Product, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>;
+
+#[derive(Debug)]
+pub enum OrderError {
+    /// The state machine does not allow the order to move to `to`.
+    InvalidTransition { from: OrderStatus, to: OrderStatus },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::InvalidTransition { from, to } => {
+                write!(f, "cannot move order from {from} to {to}")
+            }
+        }
+    }
+}
+
+impl Error for OrderError {}
+
+/// Places the orders of the users at the current prices of the products and
+/// moves them through their statuses, publishing an event named after the
+/// action for every change.
+pub struct OrderService {
+    topic: String,
+    orders: Orders,
+    users: Users,
+    products: Products,
+    broker: Broker,
+}
+
+impl OrderService {
+    pub fn new(
+        topic: &str,
+        orders: Orders,
+        users: Users,
+        products: Products,
+        broker: Broker,
+    ) -> OrderService {
+        OrderService {
+            topic: topic.to_string(),
+            orders,
+            users,
+            products,
+            broker,
+        }
+    }
+
+    pub async fn create(&self, order: NewOrder) -> Result<Order, Box<dyn Error + Send + Sync>> {
+        if order.lines.is_empty() {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "an order needs a line",
+            ))?
+        }
+        let mut lines = Vec::with_capacity(order.lines.len());
+        for line in order.lines {
+            if line.quantity <= 0 {
+                Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "quantity must be positive",
+                ))?
+            }
+            let Some(product) = self.products.get(line.product_id).await? else {
+                Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("product {} not found", line.product_id),
+                ))?
+            };
+            lines.push(OrderLine {
+                product_id: Some(product.id),
+                quantity: line.quantity,
+                price: product.price,
+            });
+        }
+        let currency = lines[0].price.currency();
+        if lines.iter().any(|line| line.price.currency() != currency) {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "the products of an order must share a currency",
+            ))?
+        }
+        let total = lines
+            .iter()
+            .map(|line| line.price.amount() * Decimal::from(line.quantity))
+            .sum();
+        let now = Utc::now();
+        let order = self
+            .orders
+            .add(Order {
+                id: Uuid::new_v4(),
+                user_id: Some(order.user_id),
+                status: OrderStatus::Pending,
+                lines,
+                total: Money::new(total, currency)?,
+                created_at: now,
+                updated_at: now,
+            })
+            .await?;
+        self.broker.send(&self.topic, "create", &order).await?;
+        Ok(order)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Order, Box<dyn Error + Send + Sync>> {
+        match self.orders.get(id).await? {
+            Some(order) => Ok(order),
+            None => Err(io::Error::from(ErrorKind::NotFound))?,
+        }
+    }
+
+    pub async fn for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<Order>, Box<dyn Error + Send + Sync>> {
+        if self.users.get(user_id).await?.is_none() {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        }
+        self.orders.for_user(user_id).await
+    }
+
+    /// Moves the order to `to` and publishes an event named after it.
+    pub async fn transition(
+        &self,
+        id: Uuid,
+        to: OrderStatus,
+    ) -> Result<Order, Box<dyn Error + Send + Sync>> {
+        let from = self.get(id).await?.status;
+        if !from.can_become(to) {
+            Err(OrderError::InvalidTransition { from, to })?
+        }
+        let Some(order) = self.orders.set_status(id, from, to).await? else {
+            // Moved by a concurrent request since it was read.
+            let from = self.get(id).await?.status;
+            Err(OrderError::InvalidTransition { from, to })?
+        };
+        self.broker.send(&self.topic, to.as_str(), &order).await?;
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::CrudService;
+    use crate::domain::dto::NewOrderLine;
+    use crate::domain::interfaces::{Database, Service};
+    use crate::infrastructure::{PostgresOrderRepository, Repository};
+    use crate::test_support::{postgres, product, usd, user, Recorder};
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn orders_capture_prices_and_keep_their_users() {
+        let postgres = postgres().await;
+        let users = Arc::new(Repository::<User> {
+            storage: postgres.clone(),
+        });
+        let products = Arc::new(Repository::<Product> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let orders = OrderService::new(
+            "order-events",
+            Arc::new(PostgresOrderRepository::new(postgres.pool())),
+            users.clone(),
+            products.clone(),
+            recorder.clone(),
+        );
+        let user_service = CrudService::new("user-events", users, recorder.clone());
+        let user = postgres.add(user()).await.unwrap();
+        let product = postgres.add(product()).await.unwrap();
+        let new_order = |quantity| NewOrder {
+            user_id: user.id,
+            lines: vec![NewOrderLine {
+                product_id: product.id,
+                quantity,
+            }],
+        };
+
+        let order = orders.create(new_order(3)).await.unwrap();
+        assert_eq!(order.status, OrderStatus::Pending);
+        assert_eq!(order.lines[0].price, usd(100));
+        assert_eq!(order.total, usd(300));
+        let error = orders.create(new_order(0)).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(ErrorKind::InvalidInput)
+        );
+
+        let error = orders
+            .transition(order.id, OrderStatus::Shipped)
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<OrderError>().is_some());
+        let paid = orders
+            .transition(order.id, OrderStatus::Paid)
+            .await
+            .unwrap();
+        assert_eq!(paid.status, OrderStatus::Paid);
+        assert_eq!(paid.lines.len(), 1);
+
+        let error = user_service.delete(user.id).await.unwrap_err();
+        let error = error.downcast_ref::<tokio_postgres::Error>().unwrap();
+        assert_eq!(
+            error.code(),
+            Some(&tokio_postgres::error::SqlState::RESTRICT_VIOLATION)
+        );
+        orders
+            .transition(order.id, OrderStatus::Cancelled)
+            .await
+            .unwrap();
+        assert_eq!(orders.for_user(user.id).await.unwrap().len(), 1);
+        user_service.delete(user.id).await.unwrap();
+        let error = orders.create(new_order(1)).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(ErrorKind::InvalidInput)
+        );
+
+        // Purging the user keeps the order and only forgets who placed it.
+        let client = postgres.pool().get().await.unwrap();
+        client
+            .execute(
+                "UPDATE Users SET deleted_at = 'epoch' WHERE id = $1",
+                &[&user.id],
+            )
+            .await
+            .unwrap();
+        let purged_before = "1970-01-02T00:00:00Z".parse().unwrap();
+        while !Database::<User>::purge(&*postgres, purged_before, 100)
+            .await
+            .unwrap()
+            .is_empty()
+        {}
+        let kept = orders.get(order.id).await.unwrap();
+        assert_eq!((kept.user_id, kept.status), (None, OrderStatus::Cancelled));
+        assert_eq!(kept.total, usd(300));
+
+        let actions: Vec<_> = recorder
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(topic, _, _)| topic == "order-events")
+            .map(|(_, action, _)| action.clone())
+            .collect();
+        assert_eq!(actions, ["create", "paid", "cancelled"]);
+    }
+}
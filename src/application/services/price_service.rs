@@ -0,0 +1,268 @@
+use crate::domain::dto::ScheduledPrice;
+use crate::domain::interfaces::{
+    Identifiable, MessageBroker, PriceTimeline, ProductUnits, Repository,
+};
+use crate::domain::models::{Envelope, PriceChange, Product};
+use chrono::Utc;
+use log::{error, info};
+use std::error::Error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+type Broker = Arc<
+    dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Box<dyn Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type Products =
+    Arc<dyn Repository<Product, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>;
+type Timeline = Arc<dyn PriceTimeline<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+type Units = Arc<dyn ProductUnits<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+/// Keeps the price timeline of the products and puts scheduled prices into
+/// effect, publishing a `price-changed` event with the new price for each.
+pub struct PriceService {
+    topic: String,
+    products: Products,
+    timeline: Timeline,
+    broker: Broker,
+    units: Option<Units>,
+    batch_size: usize,
+}
+
+impl PriceService {
+    pub fn new(
+        topic: &str,
+        products: Products,
+        timeline: Timeline,
+        broker: Broker,
+        batch_size: usize,
+    ) -> PriceService {
+        PriceService {
+            topic: topic.to_string(),
+            products,
+            timeline,
+            broker,
+            units: None,
+            batch_size,
+        }
+    }
+
+    /// Puts each scheduled price into effect in a unit of work of `units`,
+    /// the product, the timeline and the event together.
+    pub fn with_units(mut self, units: Units) -> PriceService {
+        self.units = Some(units);
+        self
+    }
+
+    /// Records the price of `product` as in effect since its last update.
+    pub async fn record(&self, product: &Product) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.timeline
+            .record(product.id, &product.price, product.updated_at)
+            .await
+    }
+
+    pub async fn history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<PriceChange>, Box<dyn Error + Send + Sync>> {
+        if self.products.get(id).await?.is_none() {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        }
+        self.timeline.history(id).await
+    }
+
+    pub async fn schedule(
+        &self,
+        id: Uuid,
+        scheduled: ScheduledPrice,
+    ) -> Result<PriceChange, Box<dyn Error + Send + Sync>> {
+        if scheduled.effective_from <= Utc::now() {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "effective_from must be in the future",
+            ))?
+        }
+        if self.products.get(id).await?.is_none() {
+            Err(io::Error::from(ErrorKind::NotFound))?
+        }
+        self.timeline
+            .schedule(id, &scheduled.price, scheduled.effective_from)
+            .await
+    }
+
+    /// Puts the due prices into effect every `interval`.
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match service.activate().await {
+                    Ok(0) => {}
+                    Ok(activated) => info!("Put {} scheduled prices into effect", activated),
+                    Err(err) => error!("Activation of scheduled prices failed: {}", err),
+                }
+            }
+        });
+    }
+
+    /// Puts the scheduled prices that are due into effect. The prices of
+    /// deleted products are dropped.
+    pub async fn activate(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut activated = 0;
+        loop {
+            let due = self.timeline.due(Utc::now(), self.batch_size).await?;
+            for change in &due {
+                let Some(units) = &self.units else {
+                    activated += self
+                        .put_into_effect(change, &self.products, &self.timeline, &self.broker)
+                        .await?;
+                    continue;
+                };
+                let unit = units.begin_products().await?;
+                let result = self
+                    .put_into_effect(
+                        change,
+                        &unit.repository(),
+                        &unit.price_timeline(),
+                        &unit.broker(),
+                    )
+                    .await;
+                match result {
+                    Ok(applied) => {
+                        unit.commit().await?;
+                        activated += applied;
+                    }
+                    Err(error) => {
+                        unit.rollback().await?;
+                        return Err(error);
+                    }
+                }
+            }
+            if due.len() < self.batch_size {
+                return Ok(activated);
+            }
+        }
+    }
+
+    /// Sets the price of `change` on its product, marks it as in effect and
+    /// announces it. Returns 0 when the product is gone and the price is
+    /// dropped, 1 otherwise.
+    async fn put_into_effect(
+        &self,
+        change: &PriceChange,
+        products: &Products,
+        timeline: &Timeline,
+        broker: &Broker,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let Some(mut product) = products.get(change.product_id).await? else {
+            timeline.cancel(change.id).await?;
+            return Ok(0);
+        };
+        product.price = change.price;
+        let changed = vec!["price".to_string()];
+        let Some(product) = products.update_fields(product, &changed).await? else {
+            timeline.cancel(change.id).await?;
+            return Ok(0);
+        };
+        timeline.apply(change.id, product.updated_at).await?;
+        let envelope = Envelope {
+            changed,
+            ..Envelope::new(&self.topic, "price-changed", &product)
+        };
+        broker.publish(envelope).await?;
+        Ok(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::{CrudService, ProductService};
+    use crate::domain::dto::Description;
+    use crate::domain::interfaces::{Database, Service};
+    use crate::infrastructure::{PostgresPriceTimeline, PostgresUnitOfWork, Repository};
+    use crate::test_support::{is_not_found, postgres, usd, Recorder};
+    use serde_json::json;
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn product_prices_are_recorded_and_scheduled() {
+        let postgres = postgres().await;
+        let repo = Arc::new(Repository::<Product> {
+            storage: postgres.clone(),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let timeline = Arc::new(PostgresPriceTimeline::new(postgres.pool()));
+        let units = Arc::new(PostgresUnitOfWork::new(postgres.clone(), recorder.clone()));
+        let prices = Arc::new(
+            PriceService::new(
+                "product-events",
+                repo.clone(),
+                timeline.clone(),
+                recorder.clone(),
+                10,
+            )
+            .with_units(units.clone()),
+        );
+        let crud = CrudService::<Product>::new("product-events", repo, recorder.clone());
+        let service = ProductService::new(crud, prices.clone()).with_units(units);
+        let description = |price| Description {
+            name: "test".to_string(),
+            price: usd(price),
+        };
+
+        let product = service.create(description(100)).await.unwrap();
+        service.update(product.id, description(100)).await.unwrap();
+        service.update(product.id, description(200)).await.unwrap();
+        let scheduled = ScheduledPrice {
+            price: usd(300),
+            effective_from: Utc::now() - chrono::Duration::seconds(1),
+        };
+        let error = prices.schedule(product.id, scheduled).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<io::Error>(),
+            Some(error) if error.kind() == ErrorKind::InvalidInput
+        ));
+        let scheduled = ScheduledPrice {
+            price: usd(300),
+            effective_from: Utc::now() + chrono::Duration::hours(1),
+        };
+        let error = prices
+            .schedule(Uuid::new_v4(), scheduled)
+            .await
+            .unwrap_err();
+        assert!(is_not_found(&error));
+
+        timeline
+            .schedule(product.id, &usd(300), Utc::now())
+            .await
+            .unwrap();
+        assert!(prices.activate().await.unwrap() >= 1);
+        let stored = Database::<Product>::get(&*postgres, product.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.price, usd(300));
+        let history: Vec<_> = prices
+            .history(product.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|change| (change.price, change.applied_at.is_some()))
+            .collect();
+        assert_eq!(
+            history,
+            [(usd(100), true), (usd(200), true), (usd(300), true)]
+        );
+        let sent = recorder.sent.lock().unwrap();
+        assert_eq!(sent.last().unwrap().1, "price-changed");
+        assert_eq!(sent.last().unwrap().2, product.id());
+        let data = recorder.data.lock().unwrap();
+        assert_eq!(data.last().unwrap(), &Some(json!({"price": usd(300)})));
+    }
+}
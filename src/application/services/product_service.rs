@@ -1,65 +1,203 @@
-use crate::domain::dto::Description;
-use crate::domain::interfaces;
-use crate::domain::interfaces::{Identifiable, MessageBroker, Repository};
-use crate::domain::models::Product;
+use crate::application::services::{CrudService, PriceService};
+use crate::domain::dto::{Batch, BatchMode, Description, Patch, Scan};
+use crate::domain::interfaces::{ProductUnit, ProductUnits, Service};
+use crate::domain::models::{Change, Product, Version};
 use axum::async_trait;
+use chrono::{DateTime, Utc};
 use std::error::Error;
-use std::io;
-use std::io::ErrorKind;
 use std::sync::Arc;
 use uuid::Uuid;
 
-pub struct ProductService;
+type Outcomes = Vec<Result<Change<Product>, Box<dyn Error + Send + Sync>>>;
+type Units = Arc<dyn ProductUnits<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+type Unit = Box<dyn ProductUnit<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+/// The CRUD operations on products, recording the prices they set on the
+/// price timeline. With units of work a change and its price are committed
+/// together.
+pub struct ProductService {
+    crud: CrudService<Product>,
+    prices: Arc<PriceService>,
+    units: Option<Units>,
+}
+
+impl ProductService {
+    pub fn new(crud: CrudService<Product>, prices: Arc<PriceService>) -> ProductService {
+        ProductService {
+            crud,
+            prices,
+            units: None,
+        }
+    }
+
+    /// Makes every change in a unit of work of `units`, with its price.
+    pub fn with_units(mut self, units: Units) -> ProductService {
+        self.units = Some(units);
+        self
+    }
+
+    /// Begins a unit of work and the CRUD service over it.
+    async fn begin(
+        units: &Units,
+        crud: &CrudService<Product>,
+    ) -> Result<(Unit, CrudService<Product>), Box<dyn Error + Send + Sync>> {
+        let unit = units.begin_products().await?;
+        let crud = crud.within(unit.repository(), unit.broker());
+        Ok((unit, crud))
+    }
+
+    /// Records the prices of the products a change left behind in `unit` and
+    /// commits it, or rolls it back when the change failed.
+    async fn finish<R>(
+        unit: Unit,
+        result: Result<R, Box<dyn Error + Send + Sync>>,
+        products: impl Fn(&R) -> Vec<&Product>,
+    ) -> Result<R, Box<dyn Error + Send + Sync>> {
+        let recorded = match &result {
+            Ok(value) => Self::record(&unit, products(value)).await,
+            Err(_) => Ok(()),
+        };
+        match result.and_then(|value| recorded.map(|_| value)) {
+            Ok(value) => {
+                unit.commit().await?;
+                Ok(value)
+            }
+            Err(error) => {
+                unit.rollback().await?;
+                Err(error)
+            }
+        }
+    }
+
+    async fn record(
+        unit: &Unit,
+        products: Vec<&Product>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let timeline = unit.price_timeline();
+        for product in products {
+            timeline
+                .record(product.id, &product.price, product.updated_at)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn changed(change: &Change<Product>) -> Vec<&Product> {
+    match change {
+        Change::Created(product) | Change::Updated(product) => vec![product],
+        Change::Deleted(_) => Vec::new(),
+    }
+}
 
 #[async_trait]
-impl interfaces::Service<Product, Description> for ProductService {
+impl Service<Product, Description> for ProductService {
     type Error = Box<dyn Error + Send + Sync>;
-    type Repository = Arc<dyn Repository<Product, Error = Self::Error, Id = Uuid> + Send + Sync>;
-    type MessageBroker =
-        Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Self::Error> + Send + Sync>;
 
-    async fn create(
-        &self,
-        dto: Description,
-        repo: Self::Repository,
-        broker: Self::MessageBroker,
-    ) -> Result<Product, Self::Error> {
-        let id = Uuid::new_v4();
-        let product = Product {
-            id,
-            name: dto.name,
-            price: dto.price,
+    async fn create(&self, dto: Description) -> Result<Product, Self::Error> {
+        let Some(units) = &self.units else {
+            let product = self.crud.create(dto).await?;
+            self.prices.record(&product).await?;
+            return Ok(product);
         };
-        repo.add(product.clone()).await?;
-        broker.send("product-events", "create", &product).await?;
-        Ok(product)
+        let (unit, crud) = Self::begin(units, &self.crud).await?;
+        let result = crud.create(dto).await;
+        Self::finish(unit, result, |product| vec![product]).await
     }
 
-    async fn update(
+    async fn get(
         &self,
         id: Uuid,
-        dto: Description,
-        repo: Self::Repository,
-        broker: Self::MessageBroker,
-    ) -> Result<(), Self::Error> {
-        let product = repo.get(id).await?;
-        if let Some(mut product) = product {
-            product.name = dto.name;
-            product.price = dto.price;
-            repo.update(product.clone()).await?;
-            broker.send("product-events", "update", &product).await
-        } else {
-            Err(io::Error::from(ErrorKind::NotFound))?
-        }
+        as_of: Option<DateTime<Utc>>,
+    ) -> Result<Option<Product>, Self::Error> {
+        self.crud.get(id, as_of).await
     }
 
-    async fn delete(
-        &self,
-        id: Uuid,
-        repo: Self::Repository,
-        broker: Self::MessageBroker,
-    ) -> Result<(), Self::Error> {
-        repo.remove(id).await?;
-        broker.send("product-events", "delete", &id).await
+    async fn list(&self, scan: &Scan) -> Result<Vec<Product>, Self::Error> {
+        self.crud.list(scan).await
+    }
+
+    async fn history(&self, id: Uuid) -> Result<Vec<Version<Product>>, Self::Error> {
+        self.crud.history(id).await
+    }
+
+    async fn update(&self, id: Uuid, dto: Description) -> Result<Product, Self::Error> {
+        let Some(units) = &self.units else {
+            let product = self.crud.update(id, dto).await?;
+            self.prices.record(&product).await?;
+            return Ok(product);
+        };
+        let (unit, crud) = Self::begin(units, &self.crud).await?;
+        let result = crud.update(id, dto).await;
+        Self::finish(unit, result, |product| vec![product]).await
+    }
+
+    async fn upsert(&self, id: Uuid, dto: Description) -> Result<Change<Product>, Self::Error> {
+        let Some(units) = &self.units else {
+            let change = self.crud.upsert(id, dto).await?;
+            for product in changed(&change) {
+                self.prices.record(product).await?;
+            }
+            return Ok(change);
+        };
+        let (unit, crud) = Self::begin(units, &self.crud).await?;
+        let result = crud.upsert(id, dto).await;
+        Self::finish(unit, result, changed).await
+    }
+
+    async fn patch(&self, id: Uuid, patch: Patch) -> Result<Product, Self::Error> {
+        let Some(units) = &self.units else {
+            let product = self.crud.patch(id, patch).await?;
+            self.prices.record(&product).await?;
+            return Ok(product);
+        };
+        let (unit, crud) = Self::begin(units, &self.crud).await?;
+        let result = crud.patch(id, patch).await;
+        Self::finish(unit, result, |product| vec![product]).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), Self::Error> {
+        self.crud.delete(id).await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<(), Self::Error> {
+        self.crud.restore(id).await
+    }
+
+    /// An atomic batch runs in one unit of work, every operation of a
+    /// partial batch in its own.
+    async fn batch(&self, batch: Batch<Description>) -> Result<Outcomes, Self::Error> {
+        let Some(units) = &self.units else {
+            let outcomes = self.crud.batch(batch).await?;
+            for product in outcomes.iter().flatten().flat_map(changed) {
+                self.prices.record(product).await?;
+            }
+            return Ok(outcomes);
+        };
+        self.crud.check_batch(&batch)?;
+        let groups = match batch.mode {
+            BatchMode::Atomic => vec![batch.operations],
+            BatchMode::Partial => batch
+                .operations
+                .into_iter()
+                .map(|operation| vec![operation])
+                .collect(),
+        };
+        let mut outcomes = Vec::new();
+        for operations in groups {
+            let (unit, crud) = Self::begin(units, &self.crud).await?;
+            let results = crud.apply_all(operations).await?;
+            if matches!(results.last(), Some(Err(_))) {
+                unit.rollback().await?;
+                outcomes.extend(results);
+                continue;
+            }
+            let results = Self::finish(unit, Ok(results), |results: &Outcomes| {
+                results.iter().flatten().flat_map(changed).collect()
+            })
+            .await?;
+            outcomes.extend(results);
+        }
+        Ok(outcomes)
     }
 }
@@ -0,0 +1,71 @@
+use crate::domain::interfaces::{Identifiable, MessageBroker, Repository};
+use chrono::Utc;
+use log::{error, info};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+type Broker = Arc<
+    dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Box<dyn Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+
+/// Permanently removes entities that have been deleted for longer than the
+/// retention period and publishes a `purge` event for each of them.
+pub struct PurgeService {
+    broker: Broker,
+    retention: Duration,
+    batch_size: usize,
+}
+
+impl PurgeService {
+    pub fn new(broker: Broker, retention: Duration, batch_size: usize) -> PurgeService {
+        PurgeService {
+            broker,
+            retention,
+            batch_size,
+        }
+    }
+
+    /// Purges the entities of `repo` every `interval`.
+    pub fn schedule<T: Send + Sync + 'static>(
+        self: &Arc<Self>,
+        topic: &'static str,
+        repo: Arc<dyn Repository<T, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>,
+        interval: Duration,
+    ) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match service.purge(topic, repo.clone()).await {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Purged {} deleted entities of {}", purged, topic),
+                    Err(err) => error!("Purge of {} failed: {}", topic, err),
+                }
+            }
+        });
+    }
+
+    pub async fn purge<T: Send + Sync>(
+        &self,
+        topic: &str,
+        repo: Arc<dyn Repository<T, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>,
+    ) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let deleted_before = Utc::now() - chrono::Duration::from_std(self.retention)?;
+        let mut purged = 0;
+        loop {
+            let ids = repo.purge(deleted_before, self.batch_size).await?;
+            for id in &ids {
+                self.broker.send(topic, "purge", id).await?;
+            }
+            purged += ids.len() as u64;
+            if ids.len() < self.batch_size {
+                return Ok(purged);
+            }
+        }
+    }
+}
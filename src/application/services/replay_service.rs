@@ -0,0 +1,98 @@
+use crate::domain::dto::Scan;
+use crate::domain::interfaces::{CheckpointStore, Identifiable, MessageBroker, Repository};
+use crate::domain::models::Envelope;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Serialize;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+type Broker = Arc<
+    dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Box<dyn Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+type Checkpoints = Arc<dyn CheckpointStore<Error = Box<dyn Error + Send + Sync>> + Send + Sync>;
+
+pub struct ReplayOptions {
+    pub ids: Option<Vec<Uuid>>,
+    pub updated_since: Option<DateTime<Utc>>,
+    /// Events per second, unthrottled when `None`.
+    pub rate: Option<f64>,
+    pub batch_size: usize,
+    pub checkpoint: String,
+    /// Ignore a checkpoint left by an interrupted run and start from the beginning.
+    pub restart: bool,
+}
+
+/// Republishes the current state of every stored entity as `snapshot` events
+/// flagged with `replay` and carrying the entity, so downstream consumers can
+/// rebuild their state.
+pub struct ReplayService {
+    broker: Broker,
+    checkpoints: Checkpoints,
+}
+
+impl ReplayService {
+    pub fn new(broker: Broker, checkpoints: Checkpoints) -> ReplayService {
+        ReplayService {
+            broker,
+            checkpoints,
+        }
+    }
+
+    pub async fn replay<T: Identifiable + Serialize + Send + Sync>(
+        &self,
+        topic: &str,
+        repo: Arc<dyn Repository<T, Error = Box<dyn Error + Send + Sync>, Id = Uuid> + Send + Sync>,
+        options: &ReplayOptions,
+    ) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let checkpoint = format!("{}:{}", options.checkpoint, topic);
+        let mut after = match self.checkpoints.load(&checkpoint).await? {
+            Some(position) if !options.restart => {
+                info!("Resuming replay of {} after {}", topic, position);
+                Some(Uuid::parse_str(&position)?)
+            }
+            _ => None,
+        };
+        let mut throttle = options.rate.map(|rate| {
+            let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let mut published = 0;
+        loop {
+            let scan = Scan {
+                ids: options.ids.clone(),
+                updated_since: options.updated_since,
+                after,
+                limit: options.batch_size,
+            };
+            let batch = repo.scan(&scan).await?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            let last = last.id();
+            for item in &batch {
+                if let Some(throttle) = throttle.as_mut() {
+                    throttle.tick().await;
+                }
+                let mut envelope = Envelope::new(topic, "snapshot", item).with_data(item)?;
+                envelope.replay = true;
+                self.broker.publish(envelope).await?;
+                published += 1;
+            }
+            self.checkpoints.save(&checkpoint, &last).await?;
+            info!("Replayed {} events to {}", published, topic);
+            after = Some(Uuid::parse_str(&last)?);
+            if batch.len() < options.batch_size {
+                break;
+            }
+        }
+        self.checkpoints.clear(&checkpoint).await?;
+        Ok(published)
+    }
+}
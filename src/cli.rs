@@ -0,0 +1,136 @@
+use crate::application::ReplayOptions;
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use uuid::Uuid;
+
+pub enum Command {
+    Serve,
+    /// `mesgmon replay [users] [products] [--ids <id,...>] [--since <rfc3339>]
+    /// [--rate <events/s>] [--batch-size <n>] [--checkpoint <name>] [--restart]`
+    Replay {
+        entities: Vec<String>,
+        options: ReplayOptions,
+    },
+}
+
+pub fn parse(
+    mut args: impl Iterator<Item = String>,
+) -> Result<Command, Box<dyn Error + Send + Sync>> {
+    match args.next().as_deref() {
+        None | Some("serve") => Ok(Command::Serve),
+        Some("replay") => parse_replay(args),
+        Some(other) => Err(format!("unknown command: {other}").into()),
+    }
+}
+
+fn parse_replay(
+    mut args: impl Iterator<Item = String>,
+) -> Result<Command, Box<dyn Error + Send + Sync>> {
+    let mut entities = Vec::new();
+    let mut options = ReplayOptions {
+        ids: None,
+        updated_since: None,
+        rate: None,
+        batch_size: 500,
+        checkpoint: "replay".to_string(),
+        restart: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "users" | "products" => entities.push(arg.clone()),
+            "--ids" => {
+                let ids = value()?
+                    .split(',')
+                    .map(|id| Uuid::parse_str(id.trim()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                options.ids = Some(ids);
+            }
+            "--since" => {
+                let since = DateTime::parse_from_rfc3339(&value()?)?;
+                options.updated_since = Some(since.with_timezone(&Utc));
+            }
+            "--rate" => options.rate = Some(value()?.parse()?),
+            "--batch-size" => options.batch_size = value()?.parse()?,
+            "--checkpoint" => options.checkpoint = value()?,
+            "--restart" => options.restart = true,
+            other => return Err(format!("unknown replay argument: {other}").into()),
+        }
+    }
+    if entities.is_empty() {
+        entities = vec!["users".to_string(), "products".to_string()];
+    }
+    Ok(Command::Replay { entities, options })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Command, Box<dyn Error + Send + Sync>> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn serves_without_a_command() {
+        assert!(matches!(run(&[]), Ok(Command::Serve)));
+        assert!(matches!(run(&["serve"]), Ok(Command::Serve)));
+        let error = run(&["migrate"]).err().unwrap();
+        assert_eq!(error.to_string(), "unknown command: migrate");
+    }
+
+    #[test]
+    fn replay_reads_its_entities_and_options() {
+        let id = Uuid::new_v4();
+        let ids = format!("{id}, {id}");
+        let args = [
+            "replay",
+            "products",
+            "--ids",
+            &ids,
+            "--since",
+            "2024-01-01T00:00:00+02:00",
+            "--rate",
+            "2.5",
+            "--batch-size",
+            "10",
+            "--checkpoint",
+            "nightly",
+            "--restart",
+        ];
+        let Ok(Command::Replay { entities, options }) = run(&args) else {
+            panic!("replay is not parsed");
+        };
+        assert_eq!(entities, ["products"]);
+        assert_eq!(options.ids, Some(vec![id, id]));
+        let since = "2023-12-31T22:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(options.updated_since, Some(since));
+        assert_eq!(options.rate, Some(2.5));
+        assert_eq!(options.batch_size, 10);
+        assert_eq!(options.checkpoint, "nightly");
+        assert!(options.restart);
+    }
+
+    #[test]
+    fn replay_defaults_to_every_entity() {
+        let Ok(Command::Replay { entities, options }) = run(&["replay"]) else {
+            panic!("replay is not parsed");
+        };
+        assert_eq!(entities, ["users", "products"]);
+        assert!(options.ids.is_none() && options.updated_since.is_none());
+        assert!(options.rate.is_none() && !options.restart);
+        assert_eq!(options.batch_size, 500);
+        assert_eq!(options.checkpoint, "replay");
+    }
+
+    #[test]
+    fn replay_rejects_bad_arguments() {
+        let error = run(&["replay", "--batch-size"]).err().unwrap();
+        assert_eq!(error.to_string(), "missing value for --batch-size");
+        let error = run(&["replay", "orders"]).err().unwrap();
+        assert_eq!(error.to_string(), "unknown replay argument: orders");
+        assert!(run(&["replay", "--ids", "not-a-uuid"]).is_err());
+        assert!(run(&["replay", "--since", "yesterday"]).is_err());
+        assert!(run(&["replay", "--batch-size", "-1"]).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// Reads the state as it was at a point in time instead of the current one.
+#[derive(Deserialize)]
+pub struct AsOf {
+    pub as_of: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Body of the batch routes.
+#[derive(Deserialize)]
+pub struct Batch<D> {
+    #[serde(default)]
+    pub mode: BatchMode,
+    pub operations: Vec<Operation<D>>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchMode {
+    /// All operations are applied in one transaction, or none of them.
+    #[default]
+    Atomic,
+    /// Every operation is applied on its own, failures do not affect the others.
+    Partial,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation<D> {
+    Create { data: D },
+    Update { id: Uuid, data: D },
+    Delete { id: Uuid },
+}
@@ -1,5 +1,25 @@
+mod as_of;
+mod batch;
 mod credentials;
+mod new_order;
+mod page;
+mod patch;
 mod product_description;
+mod reservation_request;
+mod scan;
+mod scheduled_price;
+mod status_change;
+mod stock_level;
 
+pub use as_of::AsOf;
+pub use batch::{Batch, BatchMode, Operation};
 pub use credentials::Credentials;
-pub use product_description::Description;
\ No newline at end of file
+pub use new_order::{NewOrder, NewOrderLine};
+pub use page::Page;
+pub use patch::{Patch, PatchError, PatchOperation};
+pub use product_description::Description;
+pub use reservation_request::ReservationRequest;
+pub use scan::Scan;
+pub use scheduled_price::ScheduledPrice;
+pub use status_change::StatusChange;
+pub use stock_level::StockLevel;
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct NewOrder {
+    pub user_id: Uuid,
+    pub lines: Vec<NewOrderLine>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NewOrderLine {
+    pub product_id: Uuid,
+    pub quantity: i64,
+}
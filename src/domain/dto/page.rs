@@ -0,0 +1,9 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Query of the list routes: up to `limit` entities with ids after `after`.
+#[derive(Deserialize)]
+pub struct Page {
+    pub after: Option<Uuid>,
+    pub limit: Option<usize>,
+}
@@ -0,0 +1,256 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::error;
+use std::fmt;
+
+/// Body of the patch routes, applied to the fields of the resource DTO.
+pub enum Patch {
+    /// RFC 7396 JSON Merge Patch.
+    Merge(Value),
+    /// RFC 6902 JSON Patch.
+    Json(Vec<PatchOperation>),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+#[derive(Debug)]
+pub enum PatchError {
+    /// The patch cannot be applied or does not produce a valid entity.
+    Invalid(String),
+    /// A `test` operation did not match.
+    TestFailed(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::Invalid(reason) => write!(f, "invalid patch: {reason}"),
+            PatchError::TestFailed(path) => write!(f, "patch test failed at {path}"),
+        }
+    }
+}
+
+impl error::Error for PatchError {}
+
+impl Patch {
+    /// Applies the patch to `document`, which is left unchanged on failure.
+    pub fn apply(&self, document: &mut Value) -> Result<(), PatchError> {
+        match self {
+            Patch::Merge(patch) => merge(document, patch),
+            Patch::Json(operations) => {
+                let mut patched = document.clone();
+                for operation in operations {
+                    operation.apply(&mut patched)?;
+                }
+                *document = patched;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PatchOperation {
+    fn apply(&self, document: &mut Value) -> Result<(), PatchError> {
+        match self {
+            PatchOperation::Add { path, value } => add(document, path, value.clone()),
+            PatchOperation::Remove { path } => remove(document, path).map(drop),
+            PatchOperation::Replace { path, value } => {
+                *document.pointer_mut(path).ok_or_else(|| missing(path))? = value.clone();
+                Ok(())
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(&format!("{from}/")) {
+                    return Err(PatchError::Invalid(format!(
+                        "cannot move {from} into itself"
+                    )));
+                }
+                let value = remove(document, from)?;
+                add(document, path, value)
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = document.pointer(from).ok_or_else(|| missing(from))?.clone();
+                add(document, path, value)
+            }
+            PatchOperation::Test { path, value } => match document.pointer(path) {
+                Some(current) if current == value => Ok(()),
+                _ => Err(PatchError::TestFailed(path.clone())),
+            },
+        }
+    }
+}
+
+fn merge(document: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *document = patch.clone();
+        return;
+    };
+    if !document.is_object() {
+        *document = Value::Object(Map::new());
+    }
+    if let Value::Object(fields) = document {
+        for (name, value) in patch {
+            if value.is_null() {
+                fields.remove(name);
+            } else {
+                merge(fields.entry(name.as_str()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Splits a JSON pointer into the pointer of the parent and the unescaped
+/// last token.
+fn split(path: &str) -> Result<(&str, String), PatchError> {
+    let (parent, token) = path
+        .rsplit_once('/')
+        .ok_or_else(|| PatchError::Invalid(format!("invalid path {path:?}")))?;
+    Ok((parent, token.replace("~1", "/").replace("~0", "~")))
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+    let (parent, token) = split(path)?;
+    let parent = document
+        .pointer_mut(parent)
+        .ok_or_else(|| missing(parent))?;
+    match parent {
+        Value::Object(fields) => {
+            fields.insert(token, value);
+        }
+        Value::Array(items) if token == "-" => items.push(value),
+        Value::Array(items) => {
+            let index = index(&token, items.len() + 1, path)?;
+            items.insert(index, value);
+        }
+        _ => return Err(missing(path)),
+    }
+    Ok(())
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let (parent, token) = split(path)?;
+    let parent = document
+        .pointer_mut(parent)
+        .ok_or_else(|| missing(parent))?;
+    match parent {
+        Value::Object(fields) => fields.remove(&token).ok_or_else(|| missing(path)),
+        Value::Array(items) => {
+            let index = index(&token, items.len(), path)?;
+            Ok(items.remove(index))
+        }
+        _ => Err(missing(path)),
+    }
+}
+
+/// Parses an array index below `len`.
+fn index(token: &str, len: usize, path: &str) -> Result<usize, PatchError> {
+    token
+        .parse()
+        .ok()
+        .filter(|index| *index < len)
+        .ok_or_else(|| missing(path))
+}
+
+fn missing(path: &str) -> PatchError {
+    PatchError::Invalid(format!("no value at {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn json_patch(operations: Value) -> Patch {
+        Patch::Json(serde_json::from_value(operations).unwrap())
+    }
+
+    #[test]
+    fn merge_patches_replace_and_remove_members() {
+        let mut document = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let patch = Patch::Merge(json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": {"familyName": null},
+            "tags": ["example"]
+        }));
+        patch.apply(&mut document).unwrap();
+        let expected = json!({
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        });
+        assert_eq!(document, expected);
+
+        Patch::Merge(json!({"a": {"b": "c"}}))
+            .apply(&mut document)
+            .unwrap();
+        assert_eq!(document["a"], json!({"b": "c"}));
+        Patch::Merge(json!(["a"])).apply(&mut document).unwrap();
+        assert_eq!(document, json!(["a"]));
+    }
+
+    #[test]
+    fn json_patches_apply_their_operations_in_order() {
+        let mut document = json!({"a/b": 1, "list": [1, 3], "name": "old"});
+        let patch = json_patch(json!([
+            {"op": "add", "path": "/list/1", "value": 2},
+            {"op": "add", "path": "/list/-", "value": 4},
+            {"op": "remove", "path": "/list/0"},
+            {"op": "replace", "path": "/name", "value": "new"},
+            {"op": "move", "from": "/a~1b", "path": "/moved"},
+            {"op": "copy", "from": "/name", "path": "/copied"},
+            {"op": "test", "path": "/copied", "value": "new"}
+        ]));
+        patch.apply(&mut document).unwrap();
+        let expected = json!({"list": [2, 3, 4], "name": "new", "moved": 1, "copied": "new"});
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn failed_json_patches_leave_the_document_unchanged() {
+        let original = json!({"name": "old", "list": [1]});
+        let apply = |operations| {
+            let mut document = original.clone();
+            let result = json_patch(operations).apply(&mut document);
+            assert_eq!(document, original);
+            result.unwrap_err()
+        };
+        let error = apply(json!([
+            {"op": "replace", "path": "/name", "value": "new"},
+            {"op": "test", "path": "/name", "value": "old"}
+        ]));
+        assert!(matches!(error, PatchError::TestFailed(path) if path == "/name"));
+
+        let invalid = [
+            json!([{"op": "remove", "path": "/missing"}]),
+            json!([{"op": "replace", "path": "/missing", "value": 1}]),
+            json!([{"op": "add", "path": "/list/2", "value": 1}]),
+            json!([{"op": "add", "path": "/missing/name", "value": 1}]),
+            json!([{"op": "move", "from": "/list", "path": "/list/0"}]),
+            json!([{"op": "copy", "from": "/missing", "path": "/name"}]),
+            json!([{"op": "add", "path": "name", "value": 1}]),
+        ];
+        for operations in invalid {
+            assert!(matches!(apply(operations), PatchError::Invalid(_)));
+        }
+    }
+}
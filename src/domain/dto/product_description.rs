@@ -1,7 +1,8 @@
-use serde::Deserialize;
+use crate::domain::models::Money;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Description {
     pub name: String,
-    pub price: usize,
-}
\ No newline at end of file
+    pub price: Money,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct ReservationRequest {
+    pub quantity: i64,
+    /// Seconds the units are held, the service default when absent and at
+    /// most the service maximum.
+    pub ttl_secs: Option<u64>,
+}
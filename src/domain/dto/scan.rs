@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A page of entities ordered by id, optionally restricted to a set of ids
+/// or to entities changed since a point in time.
+#[derive(Clone, Default)]
+pub struct Scan {
+    pub ids: Option<Vec<Uuid>>,
+    pub updated_since: Option<DateTime<Utc>>,
+    pub after: Option<Uuid>,
+    pub limit: usize,
+}
@@ -0,0 +1,9 @@
+use crate::domain::models::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct ScheduledPrice {
+    pub price: Money,
+    pub effective_from: DateTime<Utc>,
+}
@@ -0,0 +1,7 @@
+use crate::domain::models::OrderStatus;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct StatusChange {
+    pub status: OrderStatus,
+}
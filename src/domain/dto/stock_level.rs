@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct StockLevel {
+    pub on_hand: i64,
+    #[serde(default)]
+    pub low_stock_threshold: i64,
+}
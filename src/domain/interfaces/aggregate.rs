@@ -0,0 +1,13 @@
+use crate::domain::interfaces::Identifiable;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// An entity whose state can be stored as a stream of events.
+pub trait Aggregate: Identifiable + Serialize + DeserializeOwned {
+    /// Name of the stream type the events of this aggregate are recorded under.
+    const STREAM: &'static str;
+
+    /// Sets the timestamps kept by the event store.
+    fn set_timestamps(&mut self, created_at: DateTime<Utc>, updated_at: DateTime<Utc>);
+}
@@ -0,0 +1,10 @@
+use axum::async_trait;
+
+/// Durable positions of long running jobs, so they can resume after a restart.
+#[async_trait]
+pub trait CheckpointStore {
+    type Error;
+    async fn load(&self, name: &str) -> Result<Option<String>, Self::Error>;
+    async fn save(&self, name: &str, position: &str) -> Result<(), Self::Error>;
+    async fn clear(&self, name: &str) -> Result<(), Self::Error>;
+}
@@ -1,12 +1,53 @@
+use crate::domain::dto::Scan;
+use crate::domain::models::{Change, Version};
 use axum::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 #[async_trait]
 pub trait Database<I> {
     type Error;
-    async fn add(&self, item: I) -> Result<(), Self::Error>;
-    async fn delete(&self, id: Uuid) -> Result<(), Self::Error>;
-    async fn update(&self, item: I) -> Result<(), Self::Error>;
-    
+    /// Stores a new item and returns it with the timestamps set by the storage.
+    async fn add(&self, item: I) -> Result<I, Self::Error>;
+    /// Stores several new items at once. In a unit of work none of them is
+    /// stored when it fails.
+    async fn add_all(&self, items: Vec<I>) -> Result<Vec<I>, Self::Error>
+    where
+        I: Send + 'async_trait,
+    {
+        let mut added = Vec::with_capacity(items.len());
+        for item in items {
+            added.push(self.add(item).await?);
+        }
+        Ok(added)
+    }
+    /// Marks the item as deleted, it can be restored until it is purged.
+    /// Returns `false` if there is no such item.
+    async fn delete(&self, id: Uuid) -> Result<bool, Self::Error>;
+    /// Returns the stored item, `None` if there is no such item.
+    async fn update(&self, item: I) -> Result<Option<I>, Self::Error>;
+    /// Like `update`, writing only the given fields where the storage can.
+    async fn update_fields(&self, item: I, _fields: &[String]) -> Result<Option<I>, Self::Error>
+    where
+        I: Send + 'async_trait,
+    {
+        self.update(item).await
+    }
+    /// Stores the item under its id, replacing the item stored there. `None`
+    /// if the item stored there is deleted.
+    async fn upsert(&self, item: I) -> Result<Option<Change<I>>, Self::Error>;
+
     async fn get(&self, id: Uuid) -> Result<Option<I>, Self::Error>;
-}
\ No newline at end of file
+    async fn scan(&self, scan: &Scan) -> Result<Vec<I>, Self::Error>;
+    async fn get_as_of(&self, id: Uuid, as_of: DateTime<Utc>) -> Result<Option<I>, Self::Error>;
+    async fn history(&self, id: Uuid) -> Result<Vec<Version<I>>, Self::Error>;
+    /// Undoes a delete, returns `false` if the item is not deleted.
+    async fn restore(&self, id: Uuid) -> Result<bool, Self::Error>;
+    /// Permanently removes up to `limit` items deleted before `deleted_before`
+    /// and returns their ids.
+    async fn purge(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Uuid>, Self::Error>;
+}
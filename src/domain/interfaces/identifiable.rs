@@ -1,11 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
 use uuid::Uuid;
 
 pub trait Identifiable {
     fn id(&self) -> String;
+
+    /// Creation and last modification time, for entities that track them.
+    fn timestamps(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        None
+    }
+
+    /// Fields the events of the entity carry as their `data`.
+    fn payload(&self) -> Option<Value> {
+        None
+    }
 }
 
 impl Identifiable for Uuid {
     fn id(&self) -> String {
         self.to_string()
     }
-}
\ No newline at end of file
+}
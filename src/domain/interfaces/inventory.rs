@@ -0,0 +1,42 @@
+use crate::domain::models::{Reservation, Stock};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Stock of the products and the reservations held against it. Every
+/// operation changes the stock atomically, the available units never go
+/// negative.
+#[async_trait]
+pub trait Inventory {
+    type Error;
+    async fn stock(&self, product_id: Uuid) -> Result<Option<Stock>, Self::Error>;
+    /// Sets the units on hand. `None` if fewer than the reserved units.
+    async fn set_stock(
+        &self,
+        product_id: Uuid,
+        on_hand: i64,
+        low_stock_threshold: i64,
+    ) -> Result<Option<Stock>, Self::Error>;
+    async fn reservation(&self, id: Uuid) -> Result<Option<Reservation>, Self::Error>;
+    /// Holds `quantity` units until `expires_at`. `None` if fewer are
+    /// available.
+    async fn reserve(
+        &self,
+        product_id: Uuid,
+        quantity: i64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Option<(Reservation, Stock)>, Self::Error>;
+    /// Takes the units of a pending reservation out of the stock. `None` if
+    /// the reservation is not pending or has expired.
+    async fn confirm(&self, id: Uuid) -> Result<Option<(Reservation, Stock)>, Self::Error>;
+    /// Returns the units of a pending reservation to the stock. `None` if the
+    /// reservation is not pending.
+    async fn release(&self, id: Uuid) -> Result<Option<(Reservation, Stock)>, Self::Error>;
+    /// Returns the units of up to `limit` reservations expired at `now` to
+    /// the stock.
+    async fn expire(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Reservation>, Self::Error>;
+}
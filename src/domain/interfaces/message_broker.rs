@@ -1,7 +1,16 @@
+use crate::domain::models::Envelope;
 use axum::async_trait;
 
 #[async_trait]
 pub trait MessageBroker<M: ?Sized> {
     type Error;
     async fn send(&self, topic: &str, action: &str, message: &M) -> Result<(), Self::Error>;
+    async fn publish(&self, envelope: Envelope) -> Result<(), Self::Error>;
+    /// Publishes a batch of events, failing if any of them fails.
+    async fn publish_all(&self, envelopes: Vec<Envelope>) -> Result<(), Self::Error> {
+        for envelope in envelopes {
+            self.publish(envelope).await?;
+        }
+        Ok(())
+    }
 }
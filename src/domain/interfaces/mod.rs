@@ -1,11 +1,27 @@
+mod aggregate;
+mod checkpoint_store;
 mod database;
+mod identifiable;
+mod inventory;
 mod message_broker;
-mod service;
+mod order_repository;
+mod price_timeline;
 mod repository;
-mod identifiable;
+mod resource;
+mod service;
+mod unit_of_work;
 
+pub use aggregate::Aggregate;
+pub use checkpoint_store::CheckpointStore;
 pub use database::Database;
+pub use identifiable::Identifiable;
+pub use inventory::Inventory;
 pub use message_broker::MessageBroker;
-pub use service::Service;
+pub use order_repository::OrderRepository;
+pub use price_timeline::PriceTimeline;
 pub use repository::Repository;
-pub use identifiable::Identifiable;
\ No newline at end of file
+pub use resource::Resource;
+pub use service::Service;
+pub use unit_of_work::{
+    PriceTimelines, ProductUnit, ProductUnits, Repositories, UnitOfWork, UnitOfWorkFactory, Units,
+};
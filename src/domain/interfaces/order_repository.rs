@@ -0,0 +1,20 @@
+use crate::domain::models::{Order, OrderStatus};
+use axum::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait OrderRepository {
+    type Error;
+    /// Stores a new order of a user that is not deleted.
+    async fn add(&self, order: Order) -> Result<Order, Self::Error>;
+    async fn get(&self, id: Uuid) -> Result<Option<Order>, Self::Error>;
+    /// Orders of the user, the newest first.
+    async fn for_user(&self, user_id: Uuid) -> Result<Vec<Order>, Self::Error>;
+    /// Moves the order from `from` to `to`. `None` if it is not in `from`.
+    async fn set_status(
+        &self,
+        id: Uuid,
+        from: OrderStatus,
+        to: OrderStatus,
+    ) -> Result<Option<Order>, Self::Error>;
+}
@@ -0,0 +1,32 @@
+use crate::domain::models::{Money, PriceChange};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Prices of products over time, those in effect and those scheduled.
+#[async_trait]
+pub trait PriceTimeline {
+    type Error;
+    /// Records `price` as in effect since `at`, unless it is the price
+    /// already in effect.
+    async fn record(
+        &self,
+        product_id: Uuid,
+        price: &Money,
+        at: DateTime<Utc>,
+    ) -> Result<(), Self::Error>;
+    async fn schedule(
+        &self,
+        product_id: Uuid,
+        price: &Money,
+        effective_from: DateTime<Utc>,
+    ) -> Result<PriceChange, Self::Error>;
+    /// Prices of the product in the order they take effect.
+    async fn history(&self, product_id: Uuid) -> Result<Vec<PriceChange>, Self::Error>;
+    /// Scheduled prices due at `now`, the earliest first.
+    async fn due(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<PriceChange>, Self::Error>;
+    /// Marks a scheduled price as in effect since `at`.
+    async fn apply(&self, id: Uuid, at: DateTime<Utc>) -> Result<(), Self::Error>;
+    /// Drops a scheduled price.
+    async fn cancel(&self, id: Uuid) -> Result<(), Self::Error>;
+}
@@ -1,11 +1,27 @@
+use crate::domain::dto::Scan;
+use crate::domain::models::{Change, Version};
 use axum::async_trait;
+use chrono::{DateTime, Utc};
 
 #[async_trait]
 pub trait Repository<I: Send + Sync> {
     type Error;
     type Id;
-    async fn add(&self, item: I) -> Result<(), Self::Error>;
-    async fn remove(&self, id: Self::Id) -> Result<(), Self::Error>;
+    async fn add(&self, item: I) -> Result<I, Self::Error>;
+    async fn add_all(&self, items: Vec<I>) -> Result<Vec<I>, Self::Error>;
+    async fn remove(&self, id: Self::Id) -> Result<bool, Self::Error>;
     async fn get(&self, id: Self::Id) -> Result<Option<I>, Self::Error>;
-    async fn update(&self, item: I) -> Result<(), Self::Error>;
-}
\ No newline at end of file
+    async fn update(&self, item: I) -> Result<Option<I>, Self::Error>;
+    async fn update_fields(&self, item: I, fields: &[String]) -> Result<Option<I>, Self::Error>;
+    async fn upsert(&self, item: I) -> Result<Option<Change<I>>, Self::Error>;
+    async fn scan(&self, scan: &Scan) -> Result<Vec<I>, Self::Error>;
+    async fn get_as_of(&self, id: Self::Id, as_of: DateTime<Utc>)
+        -> Result<Option<I>, Self::Error>;
+    async fn history(&self, id: Self::Id) -> Result<Vec<Version<I>>, Self::Error>;
+    async fn restore(&self, id: Self::Id) -> Result<bool, Self::Error>;
+    async fn purge(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Self::Id>, Self::Error>;
+}
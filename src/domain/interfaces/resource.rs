@@ -0,0 +1,17 @@
+use crate::domain::interfaces::Identifiable;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An entity served by the generic CRUD routes and service.
+pub trait Resource: Identifiable + Serialize + Clone + Send + Sync + 'static {
+    /// Request body of creates and updates.
+    type Dto: Serialize + DeserializeOwned + Send + 'static;
+    /// Singular name used in error messages.
+    const NAME: &'static str;
+
+    fn create(id: Uuid, dto: Self::Dto) -> Self;
+    fn apply(&mut self, dto: Self::Dto);
+    /// The body an update would take to leave the entity as it is.
+    fn dto(&self) -> Self::Dto;
+}
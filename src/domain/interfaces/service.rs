@@ -1,28 +1,33 @@
+use crate::domain::dto::{Batch, Patch, Scan};
+use crate::domain::models::{Change, Version};
 use axum::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+/// Operations on the entities of one type. Implementations receive their
+/// repositories and broker when they are constructed, so a service may
+/// depend on as many of them as it needs.
 #[async_trait]
 pub trait Service<I, D> {
     type Error;
-    type Repository;
-    type MessageBroker;
-    async fn create(
+    async fn create(&self, dto: D) -> Result<I, Self::Error>;
+    /// Returns the current state, or the state at `as_of` when given.
+    async fn get(&self, id: Uuid, as_of: Option<DateTime<Utc>>) -> Result<Option<I>, Self::Error>;
+    async fn list(&self, scan: &Scan) -> Result<Vec<I>, Self::Error>;
+    async fn history(&self, id: Uuid) -> Result<Vec<Version<I>>, Self::Error>;
+    async fn update(&self, id: Uuid, dto: D) -> Result<I, Self::Error>;
+    /// Replaces the entity, or creates it with `id` where the service allows
+    /// creating entities with ids chosen by the client.
+    async fn upsert(&self, id: Uuid, dto: D) -> Result<Change<I>, Self::Error>;
+    /// Applies `patch` to the fields of `D` and stores the fields it changes.
+    async fn patch(&self, id: Uuid, patch: Patch) -> Result<I, Self::Error>;
+    async fn delete(&self, id: Uuid) -> Result<(), Self::Error>;
+    async fn restore(&self, id: Uuid) -> Result<(), Self::Error>;
+    /// Applies the operations of `batch` in order and returns the outcome of
+    /// each. An atomic batch stops at the first failure, which is the last
+    /// outcome, and leaves nothing applied.
+    async fn batch(
         &self,
-        dto: D,
-        repo: Self::Repository,
-        broker: Self::MessageBroker,
-    ) -> Result<I, Self::Error>;
-    async fn update(
-        &self,
-        id: Uuid,
-        dto: D,
-        repo: Self::Repository,
-        broker: Self::MessageBroker,
-    ) -> Result<(), Self::Error>;
-    async fn delete(
-        &self,
-        id: Uuid,
-        repo: Self::Repository,
-        broker: Self::MessageBroker,
-    ) -> Result<(), Self::Error>;
+        batch: Batch<D>,
+    ) -> Result<Vec<Result<Change<I>, Self::Error>>, Self::Error>;
 }
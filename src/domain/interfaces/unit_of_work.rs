@@ -0,0 +1,93 @@
+use crate::domain::interfaces::{Identifiable, MessageBroker, PriceTimeline, Repository};
+use crate::domain::models::Product;
+use axum::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Changes to several repositories that are committed or rolled back
+/// together. Messages sent to its `broker` are held back until the commit
+/// succeeds and are discarded on rollback.
+#[async_trait]
+pub trait UnitOfWork: Send + Sync {
+    type Error;
+
+    fn broker(
+        &self,
+    ) -> Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Self::Error> + Send + Sync>;
+    async fn commit(&self) -> Result<(), Self::Error>;
+    async fn rollback(&self) -> Result<(), Self::Error>;
+}
+
+/// Repositories of `I` whose changes belong to a unit of work.
+pub trait Repositories<I: Send + Sync>: UnitOfWork {
+    fn repository(&self) -> Arc<dyn Repository<I, Error = Self::Error, Id = Uuid> + Send + Sync>;
+}
+
+/// Price timeline whose changes belong to a unit of work.
+pub trait PriceTimelines: UnitOfWork {
+    fn price_timeline(&self) -> Arc<dyn PriceTimeline<Error = Self::Error> + Send + Sync>;
+}
+
+/// A unit of work changing products together with their prices.
+pub trait ProductUnit: Repositories<Product> + PriceTimelines {}
+
+impl<U: Repositories<Product> + PriceTimelines> ProductUnit for U {}
+
+#[async_trait]
+pub trait UnitOfWorkFactory: Send + Sync {
+    type Unit: UnitOfWork;
+    type Error;
+    async fn begin(&self) -> Result<Self::Unit, Self::Error>;
+}
+
+/// Begins units of work with repositories of `I`, for services that do not
+/// name the type of the unit.
+#[async_trait]
+pub trait Units<I: Send + Sync>: Send + Sync {
+    type Error;
+    async fn begin_unit(
+        &self,
+    ) -> Result<Box<dyn Repositories<I, Error = Self::Error> + Send + Sync>, Self::Error>;
+}
+
+#[async_trait]
+impl<I, F> Units<I> for F
+where
+    I: Send + Sync,
+    F: UnitOfWorkFactory,
+    F::Unit: Repositories<I> + UnitOfWork<Error = F::Error> + 'static,
+    F::Error: Send,
+{
+    type Error = F::Error;
+
+    async fn begin_unit(
+        &self,
+    ) -> Result<Box<dyn Repositories<I, Error = Self::Error> + Send + Sync>, Self::Error> {
+        Ok(Box::new(self.begin().await?))
+    }
+}
+
+/// Begins units of work over products and their prices.
+#[async_trait]
+pub trait ProductUnits: Send + Sync {
+    type Error;
+    async fn begin_products(
+        &self,
+    ) -> Result<Box<dyn ProductUnit<Error = Self::Error> + Send + Sync>, Self::Error>;
+}
+
+#[async_trait]
+impl<F> ProductUnits for F
+where
+    F: UnitOfWorkFactory,
+    F::Unit: ProductUnit + UnitOfWork<Error = F::Error> + 'static,
+    F::Error: Send,
+{
+    type Error = F::Error;
+
+    async fn begin_products(
+        &self,
+    ) -> Result<Box<dyn ProductUnit<Error = Self::Error> + Send + Sync>, Self::Error> {
+        Ok(Box::new(self.begin().await?))
+    }
+}
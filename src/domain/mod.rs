@@ -1,3 +1,4 @@
-pub mod models;
+pub mod dto;
 pub mod interfaces;
-pub mod dto;
\ No newline at end of file
+pub mod models;
+pub mod principal;
@@ -0,0 +1,29 @@
+use crate::domain::interfaces::Identifiable;
+use crate::domain::models::Envelope;
+use uuid::Uuid;
+
+/// Outcome of one write, an operation of a batch or an upsert.
+#[derive(Debug)]
+pub enum Change<T> {
+    Created(T),
+    Updated(T),
+    Deleted(Uuid),
+}
+
+impl<T: Identifiable> Change<T> {
+    pub fn id(&self) -> String {
+        match self {
+            Change::Created(item) | Change::Updated(item) => item.id(),
+            Change::Deleted(id) => id.id(),
+        }
+    }
+
+    /// The event announcing the change on `topic`.
+    pub fn envelope(&self, topic: &str) -> Envelope {
+        match self {
+            Change::Created(item) => Envelope::new(topic, "create", item),
+            Change::Updated(item) => Envelope::new(topic, "update", item),
+            Change::Deleted(id) => Envelope::new(topic, "delete", id),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::domain::interfaces::Identifiable;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Envelope {
+    pub topic: String,
+    pub action: String,
+    pub entity_id: String,
+    /// Set on snapshot events republished by a replay rather than caused by a change.
+    #[serde(default)]
+    pub replay: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Fields changed by a partial update.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<String>,
+    /// The serialized entity on snapshots, the payload of the entity on the
+    /// other events, such as the price of a product.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Envelope {
+    pub fn new<M: Identifiable + ?Sized>(topic: &str, action: &str, message: &M) -> Envelope {
+        let timestamps = message.timestamps();
+        Envelope {
+            topic: topic.to_string(),
+            action: action.to_string(),
+            entity_id: message.id(),
+            replay: false,
+            created_at: timestamps.map(|(created_at, _)| created_at),
+            updated_at: timestamps.map(|(_, updated_at)| updated_at),
+            changed: Vec::new(),
+            data: message.payload(),
+        }
+    }
+
+    /// Carries `entity` as the payload of the event.
+    pub fn with_data<E: Serialize + ?Sized>(
+        mut self,
+        entity: &E,
+    ) -> Result<Envelope, serde_json::Error> {
+        self.data = Some(serde_json::to_value(entity)?);
+        Ok(self)
+    }
+}
+
+impl Identifiable for Envelope {
+    fn id(&self) -> String {
+        self.entity_id.clone()
+    }
+
+    fn timestamps(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.created_at.zip(self.updated_at)
+    }
+}
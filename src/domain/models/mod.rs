@@ -1,5 +1,21 @@
-mod user;
+mod change;
+mod envelope;
+mod money;
+mod order;
+mod price_change;
 mod product;
+mod reservation;
+mod stock;
+mod user;
+mod version;
 
+pub use change::*;
+pub use envelope::*;
+pub use money::*;
+pub use order::*;
+pub use price_change::*;
+pub use product::*;
+pub use reservation::*;
+pub use stock::*;
 pub use user::*;
-pub use product::*;
\ No newline at end of file
+pub use version::*;
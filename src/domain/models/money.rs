@@ -0,0 +1,107 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Currencies products are sold in, by ISO 4217 code.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Eur,
+    Gbp,
+    Usd,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Usd => "USD",
+        }
+    }
+
+    /// Digits after the decimal point of the minor unit.
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            Currency::Eur | Currency::Gbp | Currency::Usd => 2,
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = MoneyError;
+
+    fn from_str(code: &str) -> Result<Currency, MoneyError> {
+        match code {
+            "EUR" => Ok(Currency::Eur),
+            "GBP" => Ok(Currency::Gbp),
+            "USD" => Ok(Currency::Usd),
+            other => Err(MoneyError::UnsupportedCurrency(other.to_string())),
+        }
+    }
+}
+
+/// A non-negative amount in a currency, with the scale of its minor unit.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(try_from = "Unchecked")]
+pub struct Money {
+    amount: Decimal,
+    currency: Currency,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, expecting = "an amount and a currency")]
+struct Unchecked {
+    amount: Decimal,
+    currency: Currency,
+}
+
+#[derive(Debug)]
+pub enum MoneyError {
+    Negative,
+    UnsupportedCurrency(String),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::Negative => write!(f, "amount must not be negative"),
+            MoneyError::UnsupportedCurrency(code) => write!(f, "unsupported currency {code:?}"),
+        }
+    }
+}
+
+impl error::Error for MoneyError {}
+
+impl Money {
+    /// Rounds `amount` to the minor unit of `currency`, halves to the even
+    /// digit.
+    pub fn new(amount: Decimal, currency: Currency) -> Result<Money, MoneyError> {
+        if amount.is_sign_negative() && !amount.is_zero() {
+            return Err(MoneyError::Negative);
+        }
+        let scale = currency.minor_units();
+        let mut amount =
+            amount.round_dp_with_strategy(scale, RoundingStrategy::MidpointNearestEven);
+        amount.rescale(scale);
+        Ok(Money { amount, currency })
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+}
+
+impl TryFrom<Unchecked> for Money {
+    type Error = MoneyError;
+
+    fn try_from(money: Unchecked) -> Result<Money, MoneyError> {
+        Money::new(money.amount, money.currency)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::domain::interfaces::Identifiable;
+use crate::domain::models::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Products ordered by a user, at the prices they had when it was placed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Order {
+    pub id: Uuid,
+    /// `None` once the user is purged, the order itself is kept.
+    pub user_id: Option<Uuid>,
+    pub status: OrderStatus,
+    pub lines: Vec<OrderLine>,
+    pub total: Money,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrderLine {
+    /// `None` once the product is purged.
+    pub product_id: Option<Uuid>,
+    pub quantity: i64,
+    /// Price of one unit when the order was placed.
+    pub price: Money,
+}
+
+/// `pending` → `paid` → `shipped` → `delivered`, an order is `cancelled`
+/// before it ships.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Pending,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn can_become(&self, next: OrderStatus) -> bool {
+        matches!(
+            (self, next),
+            (
+                OrderStatus::Pending,
+                OrderStatus::Paid | OrderStatus::Cancelled
+            ) | (
+                OrderStatus::Paid,
+                OrderStatus::Shipped | OrderStatus::Cancelled
+            ) | (OrderStatus::Shipped, OrderStatus::Delivered)
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = Box<dyn error::Error + Send + Sync>;
+
+    fn from_str(status: &str) -> Result<OrderStatus, Self::Err> {
+        match status {
+            "pending" => Ok(OrderStatus::Pending),
+            "paid" => Ok(OrderStatus::Paid),
+            "shipped" => Ok(OrderStatus::Shipped),
+            "delivered" => Ok(OrderStatus::Delivered),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            other => Err(format!("unknown order status: {other}").into()),
+        }
+    }
+}
+
+impl Identifiable for Order {
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn timestamps(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((self.created_at, self.updated_at))
+    }
+}
@@ -0,0 +1,15 @@
+use crate::domain::models::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A price of a product on its timeline.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PriceChange {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub price: Money,
+    pub effective_from: DateTime<Utc>,
+    /// When the price took effect, `None` while it is scheduled.
+    pub applied_at: Option<DateTime<Utc>>,
+}
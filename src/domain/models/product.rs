@@ -1,16 +1,66 @@
-use serde::{Serialize, Deserialize};
+use crate::domain::dto::Description;
+use crate::domain::interfaces::{Aggregate, Identifiable, Resource};
+use crate::domain::models::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use uuid::Uuid;
-use crate::domain::interfaces::Identifiable;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Product {
     pub id: Uuid,
     pub name: String,
-    pub price: usize,
+    pub price: Money,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl Identifiable for Product {
     fn id(&self) -> String {
         self.id.to_string()
     }
-}
\ No newline at end of file
+
+    fn timestamps(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((self.created_at, self.updated_at))
+    }
+
+    fn payload(&self) -> Option<Value> {
+        Some(json!({"price": self.price}))
+    }
+}
+
+impl Aggregate for Product {
+    const STREAM: &'static str = "product";
+
+    fn set_timestamps(&mut self, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) {
+        self.created_at = created_at;
+        self.updated_at = updated_at;
+    }
+}
+
+impl Resource for Product {
+    type Dto = Description;
+    const NAME: &'static str = "product";
+
+    fn create(id: Uuid, dto: Description) -> Product {
+        Product {
+            id,
+            name: dto.name,
+            price: dto.price,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn apply(&mut self, dto: Description) {
+        self.name = dto.name;
+        self.price = dto.price;
+    }
+
+    fn dto(&self) -> Description {
+        Description {
+            name: self.name.clone(),
+            price: self.price,
+        }
+    }
+}
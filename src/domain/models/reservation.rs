@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Units of a product held for a client until it confirms or releases them,
+/// or until they expire.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Reservation {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub quantity: i64,
+    pub status: ReservationStatus,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservationStatus {
+    /// The units are held.
+    Pending,
+    /// The units left the stock.
+    Confirmed,
+    /// The units went back to the stock on request.
+    Released,
+    /// The units went back to the stock when the reservation expired.
+    Expired,
+}
+
+impl ReservationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Pending => "pending",
+            ReservationStatus::Confirmed => "confirmed",
+            ReservationStatus::Released => "released",
+            ReservationStatus::Expired => "expired",
+        }
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReservationStatus {
+    type Err = Box<dyn error::Error + Send + Sync>;
+
+    fn from_str(status: &str) -> Result<ReservationStatus, Self::Err> {
+        match status {
+            "pending" => Ok(ReservationStatus::Pending),
+            "confirmed" => Ok(ReservationStatus::Confirmed),
+            "released" => Ok(ReservationStatus::Released),
+            "expired" => Ok(ReservationStatus::Expired),
+            other => Err(format!("unknown reservation status: {other}").into()),
+        }
+    }
+}
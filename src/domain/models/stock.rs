@@ -0,0 +1,37 @@
+use crate::domain::interfaces::Identifiable;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Units of a product in stock. `available` is `on_hand` less `reserved`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Stock {
+    pub product_id: Uuid,
+    pub on_hand: i64,
+    pub reserved: i64,
+    pub available: i64,
+    /// Availability at or below which the stock is low.
+    pub low_stock_threshold: i64,
+}
+
+impl Stock {
+    /// Stock of a product that was never stocked.
+    pub fn empty(product_id: Uuid) -> Stock {
+        Stock {
+            product_id,
+            on_hand: 0,
+            reserved: 0,
+            available: 0,
+            low_stock_threshold: 0,
+        }
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.available <= self.low_stock_threshold
+    }
+}
+
+impl Identifiable for Stock {
+    fn id(&self) -> String {
+        self.product_id.to_string()
+    }
+}
@@ -1,16 +1,60 @@
+use crate::domain::dto::Credentials;
+use crate::domain::interfaces::{Aggregate, Identifiable, Resource};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use crate::domain::interfaces::Identifiable;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct User {
     pub id: Uuid,
     pub name: String,
     pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl Identifiable for User {
     fn id(&self) -> String {
         self.id.to_string()
     }
+
+    fn timestamps(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((self.created_at, self.updated_at))
+    }
+}
+
+impl Aggregate for User {
+    const STREAM: &'static str = "user";
+
+    fn set_timestamps(&mut self, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) {
+        self.created_at = created_at;
+        self.updated_at = updated_at;
+    }
+}
+
+impl Resource for User {
+    type Dto = Credentials;
+    const NAME: &'static str = "user";
+
+    fn create(id: Uuid, dto: Credentials) -> User {
+        User {
+            id,
+            name: dto.name,
+            email: dto.email,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn apply(&mut self, dto: Credentials) {
+        self.name = dto.name;
+        self.email = dto.email;
+    }
+
+    fn dto(&self) -> Credentials {
+        Credentials {
+            name: self.name.clone(),
+            email: self.email.clone(),
+        }
+    }
 }
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// State of an entity during `[valid_from, valid_to)`. `state` is `None`
+/// for the version recorded by a delete.
+#[derive(Serialize, Clone)]
+pub struct Version<T> {
+    pub version: i64,
+    pub operation: String,
+    pub principal: Option<String>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub state: Option<T>,
+}
+
+impl<T: Serialize> Version<T> {
+    /// Fields that differ from `previous` as `{"field": {"from": .., "to": ..}}`.
+    pub fn changes(
+        &self,
+        previous: Option<&Version<T>>,
+    ) -> Result<Map<String, Value>, serde_json::Error> {
+        let fields = |state: Option<&T>| -> Result<Map<String, Value>, serde_json::Error> {
+            match serde_json::to_value(state)? {
+                Value::Object(fields) => Ok(fields),
+                _ => Ok(Map::new()),
+            }
+        };
+        let before = fields(previous.and_then(|previous| previous.state.as_ref()))?;
+        let after = fields(self.state.as_ref())?;
+        let mut changes = Map::new();
+        for name in before.keys().chain(after.keys()) {
+            let from = before.get(name).unwrap_or(&Value::Null);
+            let to = after.get(name).unwrap_or(&Value::Null);
+            if from != to && !changes.contains_key(name) {
+                changes.insert(name.clone(), json!({"from": from, "to": to}));
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(state: Option<Value>) -> Version<Value> {
+        Version {
+            version: 1,
+            operation: "update".to_string(),
+            principal: None,
+            valid_from: Utc::now(),
+            valid_to: None,
+            state,
+        }
+    }
+
+    #[test]
+    fn changes_list_only_the_differing_fields() {
+        let first = version(Some(json!({"name": "a", "email": "a@example.com"})));
+        let second = version(Some(json!({"name": "b", "email": "a@example.com"})));
+        let changes = second.changes(Some(&first)).unwrap();
+        assert_eq!(
+            Value::Object(changes),
+            json!({"name": {"from": "a", "to": "b"}})
+        );
+        assert!(first.changes(Some(&first)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn creates_and_deletes_change_every_field() {
+        let created = version(Some(json!({"name": "a"})));
+        let changes = created.changes(None).unwrap();
+        assert_eq!(
+            Value::Object(changes),
+            json!({"name": {"from": null, "to": "a"}})
+        );
+
+        let deleted = version(None);
+        let changes = deleted.changes(Some(&created)).unwrap();
+        assert_eq!(
+            Value::Object(changes),
+            json!({"name": {"from": "a", "to": null}})
+        );
+        assert!(deleted.changes(Some(&deleted)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,12 @@
+tokio::task_local! {
+    /// Who is making the current request, recorded with every change.
+    pub static PRINCIPAL: Option<String>;
+}
+
+/// The principal of the request being handled, if any.
+pub fn current() -> Option<String> {
+    PRINCIPAL
+        .try_with(|principal| principal.clone())
+        .ok()
+        .flatten()
+}
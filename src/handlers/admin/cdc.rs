@@ -0,0 +1,16 @@
+use crate::application::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub async fn cdc(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    match &state.change_capture {
+        Some(change_capture) => (StatusCode::OK, Json(json!(change_capture.status()))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "change data capture is not enabled"})),
+        ),
+    }
+}
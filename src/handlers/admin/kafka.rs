@@ -0,0 +1,16 @@
+use crate::application::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub async fn kafka(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    match &state.kafka_metrics {
+        Some(kafka_metrics) => (StatusCode::OK, Json(json!(kafka_metrics.view()))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "kafka is not configured"})),
+        ),
+    }
+}
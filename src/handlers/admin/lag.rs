@@ -0,0 +1,16 @@
+use crate::application::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub async fn lag(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    match &state.lag_monitor {
+        Some(lag_monitor) => (StatusCode::OK, Json(json!(lag_monitor.snapshot()))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "lag monitoring is not configured"})),
+        ),
+    }
+}
@@ -0,0 +1,30 @@
+use crate::application::AppState;
+use crate::infrastructure::Exposition;
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use std::sync::Arc;
+
+pub async fn metrics(
+    State(state): State<Arc<AppState>>,
+) -> (
+    StatusCode,
+    [(axum::http::HeaderName, &'static str); 1],
+    String,
+) {
+    let mut exposition = Exposition::default();
+    if let Some(kafka_metrics) = &state.kafka_metrics {
+        kafka_metrics.write_metrics(&mut exposition);
+    }
+    if let Some(publisher) = &state.publisher {
+        publisher.write_metrics(&mut exposition);
+    }
+    if let Some(lag_monitor) = &state.lag_monitor {
+        lag_monitor.write_metrics(&mut exposition);
+    }
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        exposition.finish(),
+    )
+}
@@ -0,0 +1,15 @@
+mod cdc;
+mod kafka;
+mod lag;
+mod metrics;
+mod projections;
+mod publisher;
+mod topics;
+
+pub use cdc::cdc;
+pub use kafka::kafka;
+pub use lag::lag;
+pub use metrics::metrics;
+pub use projections::{projections, rebuild};
+pub use publisher::publisher;
+pub use topics::{messages, topics};
@@ -0,0 +1,52 @@
+use crate::application::AppState;
+use crate::handlers::error;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+fn disabled() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "projections are not enabled"})),
+    )
+}
+
+pub async fn projections(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let Some(projector) = &state.projector else {
+        return disabled();
+    };
+    match projector.status().await {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(error) => error::internal(error),
+    }
+}
+
+pub async fn rebuild(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(projector) = &state.projector else {
+        return disabled();
+    };
+    match projector.rebuild(&name).await {
+        Ok(applied) => (
+            StatusCode::OK,
+            Json(json!({"projection": name, "applied": applied})),
+        ),
+        Err(error) => {
+            if let Some(ioerr) = error.downcast_ref::<io::Error>() {
+                if ioerr.kind() == ErrorKind::NotFound {
+                    return (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({"error": "projection not found"})),
+                    );
+                }
+            }
+            error::internal(error)
+        }
+    }
+}
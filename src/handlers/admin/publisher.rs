@@ -0,0 +1,16 @@
+use crate::application::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub async fn publisher(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    match &state.publisher {
+        Some(publisher) => (
+            StatusCode::OK,
+            Json(json!({"mode": "async", "queue": publisher.stats()})),
+        ),
+        None => (StatusCode::OK, Json(json!({"mode": "sync"}))),
+    }
+}
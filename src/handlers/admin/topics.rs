@@ -0,0 +1,51 @@
+use crate::application::AppState;
+use crate::handlers::error;
+use crate::infrastructure::MessageFilter;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+pub async fn topics(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let Some(inspector) = &state.inspector else {
+        return not_configured();
+    };
+    match inspector.topics().await {
+        Ok(topics) => (StatusCode::OK, Json(json!(topics))),
+        Err(error) => error::internal(error),
+    }
+}
+
+pub async fn messages(
+    Path((topic, partition)): Path<(String, i32)>,
+    Query(filter): Query<MessageFilter>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inspector) = &state.inspector else {
+        return not_configured();
+    };
+    match inspector.messages(topic, partition, filter).await {
+        Ok(messages) => (StatusCode::OK, Json(json!(messages))),
+        Err(error) => {
+            if let Some(ioerr) = error.downcast_ref::<io::Error>() {
+                if ioerr.kind() == ErrorKind::NotFound {
+                    return (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({"error": "topic or partition not found"})),
+                    );
+                }
+            }
+            error::internal(error)
+        }
+    }
+}
+
+fn not_configured() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "topic inspection is not configured"})),
+    )
+}
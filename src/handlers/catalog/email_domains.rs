@@ -0,0 +1,18 @@
+use super::disabled;
+use crate::application::AppState;
+use crate::handlers::error;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub async fn email_domains(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let Some(read_model) = &state.read_model else {
+        return disabled();
+    };
+    match read_model.email_domains().await {
+        Ok(domains) => (StatusCode::OK, Json(json!(domains))),
+        Err(error) => error::internal(error),
+    }
+}
@@ -0,0 +1,16 @@
+mod email_domains;
+mod products;
+
+pub use email_domains::email_domains;
+pub use products::{product, products};
+
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+fn disabled() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "projections are not enabled"})),
+    )
+}
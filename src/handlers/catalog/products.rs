@@ -0,0 +1,40 @@
+use super::disabled;
+use crate::application::AppState;
+use crate::handlers::error;
+use crate::infrastructure::CatalogQuery;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub async fn products(
+    Query(query): Query<CatalogQuery>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(read_model) = &state.read_model else {
+        return disabled();
+    };
+    match read_model.products(&query).await {
+        Ok(products) => (StatusCode::OK, Json(json!(products))),
+        Err(error) => error::internal(error),
+    }
+}
+
+pub async fn product(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(read_model) = &state.read_model else {
+        return disabled();
+    };
+    match read_model.product(id).await {
+        Ok(Some(product)) => (StatusCode::OK, Json(json!(product))),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "product not found"})),
+        ),
+        Err(error) => error::internal(error),
+    }
+}
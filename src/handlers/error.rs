@@ -0,0 +1,31 @@
+use crate::application::BatchTooLarge;
+use crate::infrastructure::{ConcurrencyConflict, PublishRejected};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::error::Error;
+
+pub fn internal(error: Box<dyn Error + Send + Sync>) -> (StatusCode, Json<Value>) {
+    if error.downcast_ref::<PublishRejected>().is_some() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({"error": error.to_string()})),
+        );
+    }
+    if error.downcast_ref::<BatchTooLarge>().is_some() {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({"error": error.to_string()})),
+        );
+    }
+    if error.downcast_ref::<ConcurrencyConflict>().is_some() {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": error.to_string()})),
+        );
+    }
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": error.to_string()})),
+    )
+}
@@ -0,0 +1,35 @@
+use crate::domain::models::Version;
+use crate::handlers::error;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::error::Error;
+
+/// Renders the versions of an entity, each with the changes made to the
+/// previous one.
+pub fn render<T: Serialize>(
+    result: Result<Vec<Version<T>>, Box<dyn Error + Send + Sync>>,
+) -> (StatusCode, Json<Value>) {
+    let versions = match result {
+        Ok(versions) if versions.is_empty() => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "no history found"})),
+            )
+        }
+        Ok(versions) => versions,
+        Err(error) => return error::internal(error),
+    };
+    let mut entries = Vec::with_capacity(versions.len());
+    for (index, version) in versions.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|previous| &versions[previous]);
+        let mut entry = json!(version);
+        match version.changes(previous) {
+            Ok(changes) => entry["changes"] = Value::Object(changes),
+            Err(error) => return error::internal(error.into()),
+        }
+        entries.push(entry);
+    }
+    (StatusCode::OK, Json(Value::Array(entries)))
+}
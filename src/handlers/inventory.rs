@@ -0,0 +1,120 @@
+use crate::application::{AppState, InventoryError};
+use crate::domain::dto::{ReservationRequest, StockLevel};
+use crate::handlers::error;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub async fn stock(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inventory) = &state.inventory else {
+        return disabled();
+    };
+    match inventory.stock(id).await {
+        Ok(stock) => (StatusCode::OK, Json(json!(stock))),
+        Err(error) => failed(error, "product"),
+    }
+}
+
+pub async fn set_stock(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(level): Json<StockLevel>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inventory) = &state.inventory else {
+        return disabled();
+    };
+    match inventory.set_stock(id, level).await {
+        Ok(stock) => (StatusCode::OK, Json(json!(stock))),
+        Err(error) => failed(error, "product"),
+    }
+}
+
+pub async fn reserve(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ReservationRequest>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inventory) = &state.inventory else {
+        return disabled();
+    };
+    match inventory.reserve(id, request).await {
+        Ok(reservation) => (StatusCode::CREATED, Json(json!(reservation))),
+        Err(error) => failed(error, "product"),
+    }
+}
+
+pub async fn reservation(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inventory) = &state.inventory else {
+        return disabled();
+    };
+    match inventory.reservation(id).await {
+        Ok(reservation) => (StatusCode::OK, Json(json!(reservation))),
+        Err(error) => failed(error, "reservation"),
+    }
+}
+
+pub async fn confirm(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inventory) = &state.inventory else {
+        return disabled();
+    };
+    match inventory.confirm(id).await {
+        Ok(reservation) => (StatusCode::OK, Json(json!(reservation))),
+        Err(error) => failed(error, "reservation"),
+    }
+}
+
+pub async fn release(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(inventory) = &state.inventory else {
+        return disabled();
+    };
+    match inventory.release(id).await {
+        Ok(reservation) => (StatusCode::OK, Json(json!(reservation))),
+        Err(error) => failed(error, "reservation"),
+    }
+}
+
+fn disabled() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "inventory is not enabled"})),
+    )
+}
+
+/// Maps the errors of the inventory, `name` is the entity the path names.
+fn failed(error: Box<dyn Error + Send + Sync>, name: &str) -> (StatusCode, Json<Value>) {
+    if error.downcast_ref::<InventoryError>().is_some() {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": error.to_string()})),
+        );
+    }
+    match error.downcast_ref::<io::Error>().map(io::Error::kind) {
+        Some(ErrorKind::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("{name} not found")})),
+        ),
+        Some(ErrorKind::InvalidInput) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": error.to_string()})),
+        ),
+        _ => error::internal(error),
+    }
+}
@@ -0,0 +1,160 @@
+use crate::domain::principal::PRINCIPAL;
+use crate::handlers::error;
+use crate::infrastructure::{Claim, IdempotencyStore};
+use axum::body::{to_bytes, Body, HttpBody};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use log::error;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// Largest request body read to fingerprint an idempotent request.
+const MAX_IDEMPOTENT_BODY: usize = 2 * 1024 * 1024;
+/// Largest response stored for repeats of an idempotent request.
+const MAX_STORED_RESPONSE: u64 = 8 * 1024 * 1024;
+
+/// Runs the request on behalf of the principal named by the `X-Principal`
+/// header, so the changes it makes are attributed to it. The header is only
+/// taken from the trusted proxies that authenticate the caller, other
+/// requests run without a principal.
+pub async fn principal(
+    State(trusted): State<Arc<[IpAddr]>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    let principal = request
+        .headers()
+        .get("x-principal")
+        .filter(|_| peer.is_some_and(|peer| trusted.contains(&peer)))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    PRINCIPAL.scope(principal, next.run(request)).await
+}
+
+/// Lets a request through when it carries the admin token as a bearer
+/// token. Without a configured token the admin routes are closed.
+pub async fn admin(
+    State(token): State<Option<Arc<str>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = token else {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "admin routes are closed, no admin token is configured"})),
+        )
+            .into_response();
+    };
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if given.is_some_and(|given| same_secret(given.as_bytes(), token.as_bytes())) {
+        return next.run(request).await;
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(json!({"error": "admin token required"})),
+    )
+        .into_response()
+}
+
+/// Compares in time independent of where the inputs differ.
+fn same_secret(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Answers a `POST` repeated with the same `Idempotency-Key` header with the
+/// stored response of the first one instead of running it again. Server
+/// errors are not stored, the request can be retried with the same key.
+pub async fn idempotency(
+    State(store): State<Arc<IdempotencyStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = request
+        .headers()
+        .get("idempotency-key")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let Some(key) = key.filter(|_| request.method() == Method::POST) else {
+        return next.run(request).await;
+    };
+    let scope = request.uri().path().to_string();
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, MAX_IDEMPOTENT_BODY).await else {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({"error": "request body is too large"})),
+        )
+            .into_response();
+    };
+    let pending = match store.claim(&scope, &key, &body).await {
+        Ok(Claim::Acquired(pending)) => pending,
+        Ok(Claim::Replay { status, body }) => {
+            return (
+                StatusCode::from_u16(status).unwrap_or(StatusCode::OK),
+                [
+                    (header::CONTENT_TYPE, "application/json"),
+                    (
+                        header::HeaderName::from_static("idempotent-replayed"),
+                        "true",
+                    ),
+                ],
+                body,
+            )
+                .into_response()
+        }
+        Ok(Claim::Mismatch) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"error": "Idempotency-Key was used for a different request"})),
+            )
+                .into_response()
+        }
+        Err(err) => return error::internal(err).into_response(),
+    };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        if let Err(err) = pending.release().await {
+            error!("Failed to release idempotency key {}: {}", key, err);
+        }
+        return response;
+    }
+    // Responses of unknown or excessive size are passed on without storing
+    // them, the key is released so that a repeat runs the request again.
+    let size = response.body().size_hint().exact();
+    if size.is_none_or(|size| size > MAX_STORED_RESPONSE) {
+        if let Err(err) = pending.release().await {
+            error!("Failed to release idempotency key {}: {}", key, err);
+        }
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_STORED_RESPONSE as usize).await {
+        Ok(body) => body,
+        Err(err) => return error::internal(err.into()).into_response(),
+    };
+    if let Err(err) = pending.complete(parts.status.as_u16(), &body).await {
+        error!(
+            "Failed to store the response for idempotency key {}: {}",
+            key, err
+        );
+    }
+    Response::from_parts(parts, Body::from(body))
+}
@@ -1,4 +1,9 @@
-pub mod product;
-pub mod user;
-
-use crate::domain::dto::Credentials;
\ No newline at end of file
+pub mod admin;
+pub mod catalog;
+mod error;
+mod history;
+pub mod inventory;
+pub mod middleware;
+pub mod orders;
+pub mod prices;
+pub mod resource;
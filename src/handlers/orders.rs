@@ -0,0 +1,94 @@
+use crate::application::{AppState, OrderError};
+use crate::domain::dto::{NewOrder, StatusChange};
+use crate::handlers::error;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    Json(order): Json<NewOrder>,
+) -> (StatusCode, Json<Value>) {
+    let Some(orders) = &state.orders else {
+        return disabled();
+    };
+    match orders.create(order).await {
+        Ok(order) => (StatusCode::CREATED, Json(json!(order))),
+        Err(error) => failed(error, "order"),
+    }
+}
+
+pub async fn get(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(orders) = &state.orders else {
+        return disabled();
+    };
+    match orders.get(id).await {
+        Ok(order) => (StatusCode::OK, Json(json!(order))),
+        Err(error) => failed(error, "order"),
+    }
+}
+
+/// Orders of a user, the newest first.
+pub async fn for_user(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(orders) = &state.orders else {
+        return disabled();
+    };
+    match orders.for_user(id).await {
+        Ok(orders) => (StatusCode::OK, Json(json!(orders))),
+        Err(error) => failed(error, "user"),
+    }
+}
+
+pub async fn set_status(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(change): Json<StatusChange>,
+) -> (StatusCode, Json<Value>) {
+    let Some(orders) = &state.orders else {
+        return disabled();
+    };
+    match orders.transition(id, change.status).await {
+        Ok(order) => (StatusCode::OK, Json(json!(order))),
+        Err(error) => failed(error, "order"),
+    }
+}
+
+fn disabled() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "orders are not enabled"})),
+    )
+}
+
+/// Maps the errors of the orders, `name` is the entity the path names.
+fn failed(error: Box<dyn Error + Send + Sync>, name: &str) -> (StatusCode, Json<Value>) {
+    if error.downcast_ref::<OrderError>().is_some() {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": error.to_string()})),
+        );
+    }
+    match error.downcast_ref::<io::Error>().map(io::Error::kind) {
+        Some(ErrorKind::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("{name} not found")})),
+        ),
+        Some(ErrorKind::InvalidInput) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": error.to_string()})),
+        ),
+        _ => error::internal(error),
+    }
+}
@@ -0,0 +1,62 @@
+use crate::application::AppState;
+use crate::domain::dto::ScheduledPrice;
+use crate::handlers::error;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Price timeline of a product, including the scheduled prices.
+pub async fn history(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(prices) = &state.prices else {
+        return disabled();
+    };
+    match prices.history(id).await {
+        Ok(history) => (StatusCode::OK, Json(json!(history))),
+        Err(error) => failed(error),
+    }
+}
+
+/// Schedules a price to take effect at a future time.
+pub async fn schedule(
+    Path(id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(scheduled): Json<ScheduledPrice>,
+) -> (StatusCode, Json<Value>) {
+    let Some(prices) = &state.prices else {
+        return disabled();
+    };
+    match prices.schedule(id, scheduled).await {
+        Ok(change) => (StatusCode::CREATED, Json(json!(change))),
+        Err(error) => failed(error),
+    }
+}
+
+fn disabled() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": "price timelines are not enabled"})),
+    )
+}
+
+fn failed(error: Box<dyn Error + Send + Sync>) -> (StatusCode, Json<Value>) {
+    match error.downcast_ref::<io::Error>().map(io::Error::kind) {
+        Some(ErrorKind::NotFound) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "product not found"})),
+        ),
+        Some(ErrorKind::InvalidInput) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": error.to_string()})),
+        ),
+        _ => error::internal(error),
+    }
+}
@@ -0,0 +1,110 @@
+use crate::application::ResourceService;
+use crate::domain::dto::{Batch, BatchMode};
+use crate::domain::interfaces::Resource;
+use crate::domain::models::Change;
+use crate::handlers::error;
+use crate::handlers::resource::{is_not_found, is_unique_violation, not_found, restriction, Error};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::io;
+use std::io::ErrorKind;
+
+/// Serves `POST {path}:batch`. The router only matches `{path}` followed by
+/// a parameter, any other suffix is answered as a missing route.
+pub async fn batch<T: Resource>(
+    Path(action): Path<String>,
+    State(service): State<ResourceService<T>>,
+    batch: Result<Json<Batch<T::Dto>>, JsonRejection>,
+) -> (StatusCode, Json<Value>) {
+    if action != ":batch" {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "no such route"})),
+        );
+    }
+    let batch = match batch {
+        Ok(Json(batch)) => batch,
+        Err(rejection) => {
+            return (
+                rejection.status(),
+                Json(json!({"error": rejection.body_text()})),
+            )
+        }
+    };
+    let mode = batch.mode;
+    let mut results = match service.batch(batch).await {
+        Ok(results) => results,
+        Err(error) if is_unsupported(&error) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": error.to_string()})),
+            )
+        }
+        Err(error) => return error::internal(error),
+    };
+    if mode == BatchMode::Atomic && matches!(results.last(), Some(Err(_))) {
+        let Some(Err(error)) = results.pop() else {
+            unreachable!()
+        };
+        let (status, Json(body)) = failure::<T>(error);
+        return (
+            status,
+            Json(json!({"error": body["error"], "index": results.len()})),
+        );
+    }
+    let results: Vec<_> = results
+        .into_iter()
+        .map(|result| match result {
+            Ok(change) => {
+                let id = change.id();
+                match change {
+                    Change::Created(item) => json!({"status": 201, "id": id, "item": item}),
+                    Change::Updated(item) => json!({"status": 200, "id": id, "item": item}),
+                    Change::Deleted(_) => json!({"status": 204, "id": id}),
+                }
+            }
+            Err(error) => {
+                let (status, Json(body)) = failure::<T>(error);
+                json!({"status": status.as_u16(), "error": body["error"]})
+            }
+        })
+        .collect();
+    (StatusCode::OK, Json(json!({"results": results})))
+}
+
+/// Answers the other methods on `{path}` followed by a parameter.
+pub async fn other(Path(action): Path<String>) -> (StatusCode, Json<Value>) {
+    if action == ":batch" {
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            Json(json!({"error": "batches are only posted"})),
+        )
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "no such route"})),
+        )
+    }
+}
+
+fn failure<T: Resource>(error: Error) -> (StatusCode, Json<Value>) {
+    if is_not_found(&error) {
+        not_found::<T>()
+    } else if is_unique_violation(&error) {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{} already exists", T::NAME)})),
+        )
+    } else if let Some(reason) = restriction(&error) {
+        (StatusCode::CONFLICT, Json(json!({"error": reason})))
+    } else {
+        error::internal(error)
+    }
+}
+
+fn is_unsupported(error: &Error) -> bool {
+    matches!(error.downcast_ref::<io::Error>(), Some(error) if error.kind() == ErrorKind::Unsupported)
+}
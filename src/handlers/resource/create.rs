@@ -0,0 +1,23 @@
+use crate::application::ResourceService;
+use crate::domain::interfaces::Resource;
+use crate::handlers::error;
+use crate::handlers::resource::is_unique_violation;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+pub async fn create<T: Resource>(
+    State(service): State<ResourceService<T>>,
+    Json(data): Json<T::Dto>,
+) -> (StatusCode, Json<Value>) {
+    let result = service.create(data).await;
+    match result {
+        Ok(item) => (StatusCode::CREATED, Json(json!(item))),
+        Err(error) if is_unique_violation(&error) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{} already exists", T::NAME)})),
+        ),
+        Err(error) => error::internal(error),
+    }
+}
@@ -0,0 +1,24 @@
+use crate::application::ResourceService;
+use crate::domain::interfaces::Resource;
+use crate::handlers::error;
+use crate::handlers::resource::{is_not_found, not_found, restriction};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+pub async fn delete<T: Resource>(
+    Path(id): Path<Uuid>,
+    State(service): State<ResourceService<T>>,
+) -> (StatusCode, Json<Value>) {
+    let result = service.delete(id).await;
+    match result {
+        Ok(_) => (StatusCode::NO_CONTENT, Json(Value::default())),
+        Err(error) if is_not_found(&error) => not_found::<T>(),
+        Err(error) => match restriction(&error) {
+            Some(reason) => (StatusCode::CONFLICT, Json(json!({"error": reason}))),
+            None => error::internal(error),
+        },
+    }
+}
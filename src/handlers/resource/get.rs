@@ -0,0 +1,22 @@
+use crate::application::ResourceService;
+use crate::domain::dto::AsOf;
+use crate::domain::interfaces::Resource;
+use crate::handlers::error;
+use crate::handlers::resource::not_found;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+pub async fn get<T: Resource>(
+    Path(id): Path<Uuid>,
+    Query(query): Query<AsOf>,
+    State(service): State<ResourceService<T>>,
+) -> (StatusCode, Json<Value>) {
+    match service.get(id, query.as_of).await {
+        Ok(Some(item)) => (StatusCode::OK, Json(json!(item))),
+        Ok(None) => not_found::<T>(),
+        Err(error) => error::internal(error),
+    }
+}
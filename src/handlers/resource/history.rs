@@ -0,0 +1,15 @@
+use crate::application::ResourceService;
+use crate::domain::interfaces::Resource;
+use crate::handlers::history;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub async fn history<T: Resource>(
+    Path(id): Path<Uuid>,
+    State(service): State<ResourceService<T>>,
+) -> (StatusCode, Json<Value>) {
+    history::render(service.history(id).await)
+}
@@ -0,0 +1,34 @@
+use crate::application::ResourceService;
+use crate::domain::dto::{Page, Scan};
+use crate::domain::interfaces::Resource;
+use crate::handlers::error;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// Lists the entities ordered by id, `next` is the `after` of the next page.
+pub async fn list<T: Resource>(
+    Query(page): Query<Page>,
+    State(service): State<ResourceService<T>>,
+) -> (StatusCode, Json<Value>) {
+    let limit = page.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let scan = Scan {
+        after: page.after,
+        limit,
+        ..Scan::default()
+    };
+    match service.list(&scan).await {
+        Ok(items) => {
+            let next = match items.last() {
+                Some(last) if items.len() == limit => Some(last.id()),
+                _ => None,
+            };
+            (StatusCode::OK, Json(json!({"items": items, "next": next})))
+        }
+        Err(error) => error::internal(error),
+    }
+}
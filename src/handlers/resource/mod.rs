@@ -0,0 +1,71 @@
+mod batch;
+mod create;
+mod delete;
+mod get;
+mod history;
+mod list;
+mod patch;
+mod restore;
+mod update;
+
+use crate::application::ResourceService;
+use crate::domain::interfaces::Resource;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::error;
+use std::io;
+use std::io::ErrorKind;
+use tokio_postgres::error::SqlState;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Routes to create, list, read, update, patch, delete and restore the
+/// entities of `T` under `path`, one at a time or in batches.
+pub fn routes<T: Resource>(path: &str, service: ResourceService<T>) -> Router {
+    Router::new()
+        .route(path, get(list::list::<T>).post(create::create::<T>))
+        // A parameter, matchit has no literal `:` in a segment.
+        .route(
+            &format!("{path}:action"),
+            post(batch::batch::<T>).fallback(batch::other),
+        )
+        .route(
+            &format!("{path}/:id"),
+            get(get::get::<T>)
+                .put(update::update::<T>)
+                .patch(patch::patch::<T>)
+                .delete(delete::delete::<T>),
+        )
+        .route(&format!("{path}/:id/history"), get(history::history::<T>))
+        .route(&format!("{path}/:id/restore"), post(restore::restore::<T>))
+        .with_state(service)
+}
+
+fn not_found<T: Resource>() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": format!("{} not found", T::NAME)})),
+    )
+}
+
+fn is_not_found(error: &Error) -> bool {
+    matches!(error.downcast_ref::<io::Error>(), Some(error) if error.kind() == ErrorKind::NotFound)
+}
+
+fn is_unique_violation(error: &Error) -> bool {
+    matches!(
+        error.downcast_ref::<tokio_postgres::Error>(),
+        Some(error) if error.code() == Some(&SqlState::UNIQUE_VIOLATION)
+    )
+}
+
+/// Message of a trigger that refused the change, as deleting a user with open
+/// orders.
+fn restriction(error: &Error) -> Option<String> {
+    let error = error
+        .downcast_ref::<tokio_postgres::Error>()?
+        .as_db_error()?;
+    (error.code() == &SqlState::RESTRICT_VIOLATION).then(|| error.message().to_string())
+}
@@ -0,0 +1,66 @@
+use crate::application::ResourceService;
+use crate::domain::dto::{Patch, PatchError};
+use crate::domain::interfaces::Resource;
+use crate::handlers::error;
+use crate::handlers::resource::{is_not_found, is_unique_violation, not_found};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::Json;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+pub async fn patch<T: Resource>(
+    Path(id): Path<Uuid>,
+    State(service): State<ResourceService<T>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .unwrap_or_default()
+        .trim();
+    let patch = match content_type {
+        "application/merge-patch+json" => serde_json::from_slice(&body).map(Patch::Merge),
+        "application/json-patch+json" => serde_json::from_slice(&body).map(Patch::Json),
+        _ => {
+            return (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                Json(json!({
+                    "error": "expected application/merge-patch+json or application/json-patch+json"
+                })),
+            )
+        }
+    };
+    let patch = match patch {
+        Ok(patch) => patch,
+        Err(error) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": error.to_string()})),
+            )
+        }
+    };
+    let result = service.patch(id, patch).await;
+    match result {
+        Ok(item) => (StatusCode::OK, Json(json!(item))),
+        Err(error) if is_not_found(&error) => not_found::<T>(),
+        Err(error) if is_unique_violation(&error) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{} with this data already exists", T::NAME)})),
+        ),
+        Err(error) => match error.downcast_ref::<PatchError>() {
+            Some(PatchError::Invalid(_)) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"error": error.to_string()})),
+            ),
+            Some(PatchError::TestFailed(_)) => (
+                StatusCode::CONFLICT,
+                Json(json!({"error": error.to_string()})),
+            ),
+            None => error::internal(error),
+        },
+    }
+}
@@ -0,0 +1,28 @@
+use crate::application::ResourceService;
+use crate::domain::interfaces::Resource;
+use crate::handlers::error;
+use crate::handlers::resource::{is_not_found, is_unique_violation};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+pub async fn restore<T: Resource>(
+    Path(id): Path<Uuid>,
+    State(service): State<ResourceService<T>>,
+) -> (StatusCode, Json<Value>) {
+    let result = service.restore(id).await;
+    match result {
+        Ok(_) => (StatusCode::OK, Json(Value::default())),
+        Err(error) if is_not_found(&error) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("no deleted {} with this id", T::NAME)})),
+        ),
+        Err(error) if is_unique_violation(&error) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": format!("a {} with this data already exists", T::NAME)})),
+        ),
+        Err(error) => error::internal(error),
+    }
+}
@@ -0,0 +1,44 @@
+use crate::application::ResourceService;
+use crate::domain::interfaces::Resource;
+use crate::domain::models::Change;
+use crate::handlers::error;
+use crate::handlers::resource::{is_not_found, is_unique_violation, not_found, Error};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::io;
+use std::io::ErrorKind;
+use uuid::Uuid;
+
+/// Replaces the entity, or creates it with the id of the path where the
+/// resource allows it.
+pub async fn update<T: Resource>(
+    Path(id): Path<Uuid>,
+    State(service): State<ResourceService<T>>,
+    Json(data): Json<T::Dto>,
+) -> (StatusCode, Json<Value>) {
+    let result = service.upsert(id, data).await;
+    match result {
+        Ok(Change::Created(item)) => (StatusCode::CREATED, Json(json!(item))),
+        Ok(Change::Updated(item)) => (StatusCode::OK, Json(json!(item))),
+        Ok(Change::Deleted(_)) => not_found::<T>(),
+        Err(error) if is_not_found(&error) => not_found::<T>(),
+        Err(error) if is_unique_violation(&error) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{} with this data already exists", T::NAME)})),
+        ),
+        Err(error) if is_deleted(&error) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": format!("{} is deleted, restore it first", T::NAME)})),
+        ),
+        Err(error) => error::internal(error),
+    }
+}
+
+fn is_deleted(error: &Error) -> bool {
+    matches!(
+        error.downcast_ref::<io::Error>(),
+        Some(error) if error.kind() == ErrorKind::AlreadyExists
+    )
+}
@@ -1,7 +0,0 @@
-mod create;
-mod update;
-mod delete;
-
-pub use delete::delete;
-pub use update::update;
-pub use create::create;
\ No newline at end of file
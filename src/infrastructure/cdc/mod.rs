@@ -0,0 +1,239 @@
+mod pgoutput;
+
+use crate::domain::interfaces::{Identifiable, MessageBroker};
+use crate::domain::models::Envelope;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use log::{error, info, warn};
+use pgoutput::{Message, Relation};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+type Broker = Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Error> + Send + Sync>;
+
+/// Captured tables and the topics their changes are published to.
+const TABLES: &[(&str, &str)] = &[("users", "user-events"), ("products", "product-events")];
+
+#[derive(Serialize, Clone, Default)]
+pub struct CaptureStatus {
+    pub slot: String,
+    pub confirmed_lsn: Option<String>,
+    pub published: u64,
+    pub last_error: Option<String>,
+}
+
+/// Publishes row changes of the captured tables from a logical replication
+/// slot decoded with `pgoutput`. The slot is advanced past a transaction only
+/// after all of its events were delivered, so a restart resumes from the last
+/// confirmed LSN and may at most repeat the events of one transaction.
+pub struct ChangeCapture {
+    pool: Pool,
+    slot: String,
+    publication: String,
+    batch_size: i32,
+    broker: Broker,
+    status: Mutex<CaptureStatus>,
+}
+
+impl ChangeCapture {
+    pub fn new(
+        pool: Pool,
+        slot: &str,
+        publication: &str,
+        batch_size: i32,
+        broker: Broker,
+    ) -> ChangeCapture {
+        ChangeCapture {
+            pool,
+            slot: slot.to_string(),
+            publication: publication.to_string(),
+            batch_size,
+            broker,
+            status: Mutex::new(CaptureStatus {
+                slot: slot.to_string(),
+                ..CaptureStatus::default()
+            }),
+        }
+    }
+
+    pub fn status(&self) -> CaptureStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub async fn start(self: &Arc<Self>, interval: Duration) -> Result<(), Error> {
+        self.ensure_slot().await?;
+        let capture = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let result = capture.poll().await;
+                let idle = match &result {
+                    Ok(changes) => *changes == 0,
+                    Err(_) => true,
+                };
+                if let Err(err) = result {
+                    error!("Change capture failed: {}", err);
+                    capture.status.lock().unwrap().last_error = Some(err.to_string());
+                }
+                if idle {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn ensure_slot(&self) -> Result<(), Error> {
+        let connection = self.pool.get().await?;
+        let row = connection
+            .query_opt(
+                "SELECT confirmed_flush_lsn::TEXT AS lsn FROM pg_replication_slots WHERE slot_name = $1",
+                &[&self.slot],
+            )
+            .await?;
+        let lsn: Option<String> = match row {
+            Some(row) => row.get("lsn"),
+            None => {
+                info!("Creating logical replication slot {}", self.slot);
+                let row = connection
+                    .query_one(
+                        "SELECT lsn::TEXT AS lsn FROM pg_create_logical_replication_slot($1, 'pgoutput')",
+                        &[&self.slot],
+                    )
+                    .await?;
+                row.get("lsn")
+            }
+        };
+        info!("Capturing changes from slot {} at {:?}", self.slot, lsn);
+        self.status.lock().unwrap().confirmed_lsn = lsn;
+        Ok(())
+    }
+
+    /// Handles the complete transactions available in the slot and returns
+    /// the number of decoded messages.
+    async fn poll(&self) -> Result<usize, Error> {
+        let connection = self.pool.get().await?;
+        let rows = connection
+            .query(
+                "SELECT data FROM pg_logical_slot_peek_binary_changes(
+                    $1, NULL, $2, 'proto_version', '1', 'publication_names', $3
+                )",
+                &[&self.slot, &self.batch_size, &self.publication],
+            )
+            .await?;
+        let mut relations: HashMap<u32, Relation> = HashMap::new();
+        let mut pending = Vec::new();
+        for row in &rows {
+            let data: &[u8] = row.get("data");
+            match pgoutput::decode(data)? {
+                Message::Begin => pending.clear(),
+                Message::Relation(relation) => {
+                    relations.insert(relation.id, relation);
+                }
+                Message::Insert { relation, new } => {
+                    pending.extend(Self::envelope(&relations, relation, "create", &new));
+                }
+                Message::Update { relation, old, new } => {
+                    let action = Self::update_action(&relations, relation, old.as_ref(), &new);
+                    pending.extend(Self::envelope(&relations, relation, action, &new));
+                }
+                Message::Delete { relation, old } => {
+                    pending.extend(Self::envelope(&relations, relation, "purge", &old));
+                }
+                Message::Truncate {
+                    relations: truncated,
+                } => {
+                    for relation in truncated.iter().filter_map(|id| relations.get(id)) {
+                        warn!(
+                            "Table {}.{} was truncated, no events are published for it",
+                            relation.namespace, relation.name
+                        );
+                    }
+                }
+                Message::Commit { end_lsn } => {
+                    for envelope in pending.drain(..) {
+                        self.broker.publish(envelope).await?;
+                        self.status.lock().unwrap().published += 1;
+                    }
+                    let lsn = pgoutput::format_lsn(end_lsn);
+                    connection
+                        .execute(
+                            "SELECT pg_replication_slot_advance($1, $2::TEXT::PG_LSN)",
+                            &[&self.slot, &lsn],
+                        )
+                        .await?;
+                    let mut status = self.status.lock().unwrap();
+                    status.confirmed_lsn = Some(lsn);
+                    status.last_error = None;
+                }
+                Message::Other => {}
+            }
+        }
+        Ok(rows.len())
+    }
+
+    /// Rows are deleted by setting `deleted_at` and restored by clearing it,
+    /// removing a row purges it.
+    fn update_action(
+        relations: &HashMap<u32, Relation>,
+        relation: u32,
+        old: Option<&pgoutput::Tuple>,
+        new: &pgoutput::Tuple,
+    ) -> &'static str {
+        let Some(relation) = relations.get(&relation) else {
+            return "update";
+        };
+        let deleted = relation.value(new, "deleted_at").is_some();
+        let was_deleted = old
+            .and_then(|old| relation.value(old, "deleted_at"))
+            .is_some();
+        match (was_deleted, deleted) {
+            (false, true) => "delete",
+            (true, false) => "restore",
+            _ => "update",
+        }
+    }
+
+    fn envelope(
+        relations: &HashMap<u32, Relation>,
+        relation: u32,
+        action: &str,
+        tuple: &pgoutput::Tuple,
+    ) -> Option<Envelope> {
+        let relation = relations.get(&relation)?;
+        let (_, topic) = TABLES.iter().find(|(table, _)| *table == relation.name)?;
+        let id = Uuid::parse_str(relation.value(tuple, "id")?).ok()?;
+        let mut envelope = Envelope::new(topic, action, &id);
+        envelope.created_at = Self::timestamp(relation, tuple, "created_at");
+        envelope.updated_at = Self::timestamp(relation, tuple, "updated_at");
+        envelope.data = Self::price(relation, tuple).map(|price| json!({"price": price}));
+        Some(envelope)
+    }
+
+    /// Parses a `money_amount` column in its text output format,
+    /// `(amount,currency)`.
+    fn price(relation: &Relation, tuple: &pgoutput::Tuple) -> Option<Value> {
+        let value = relation.value(tuple, "price")?;
+        let (amount, currency) = value
+            .strip_prefix('(')?
+            .strip_suffix(')')?
+            .split_once(',')?;
+        Some(json!({"amount": amount, "currency": currency}))
+    }
+
+    /// Parses a `timestamptz` column in its text output format.
+    fn timestamp(
+        relation: &Relation,
+        tuple: &pgoutput::Tuple,
+        column: &str,
+    ) -> Option<DateTime<Utc>> {
+        let value = relation.value(tuple, column)?;
+        let timestamp = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z").ok()?;
+        Some(timestamp.with_timezone(&Utc))
+    }
+}
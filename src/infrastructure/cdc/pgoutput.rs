@@ -0,0 +1,322 @@
+use std::error;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Column values of a row, `None` for SQL NULL and for unchanged TOAST values.
+pub type Tuple = Vec<Option<String>>;
+
+pub struct Relation {
+    pub id: u32,
+    pub namespace: String,
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+impl Relation {
+    pub fn value<'a>(&self, tuple: &'a Tuple, column: &str) -> Option<&'a str> {
+        let index = self.columns.iter().position(|name| name == column)?;
+        tuple.get(index)?.as_deref()
+    }
+}
+
+/// Messages of the `pgoutput` logical decoding plugin, protocol version 1.
+pub enum Message {
+    Begin,
+    Commit {
+        end_lsn: u64,
+    },
+    Relation(Relation),
+    Insert {
+        relation: u32,
+        new: Tuple,
+    },
+    Update {
+        relation: u32,
+        /// Present when the table has a replica identity other than the
+        /// primary key.
+        old: Option<Tuple>,
+        new: Tuple,
+    },
+    Delete {
+        relation: u32,
+        old: Tuple,
+    },
+    Truncate {
+        relations: Vec<u32>,
+    },
+    /// Origin, type and logical messages, which carry no row changes.
+    Other,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], Error> {
+        if self.data.len() < len {
+            return Err("truncated pgoutput message".into());
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let end = self
+            .data
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or("unterminated string in pgoutput message")?;
+        let value = String::from_utf8(self.take(end)?.to_vec())?;
+        self.take(1)?;
+        Ok(value)
+    }
+
+    fn tuple(&mut self) -> Result<Tuple, Error> {
+        let columns = self.u16()?;
+        let mut tuple = Vec::with_capacity(columns as usize);
+        for _ in 0..columns {
+            match self.u8()? {
+                b'n' | b'u' => tuple.push(None),
+                b't' => {
+                    let len = self.u32()? as usize;
+                    tuple.push(Some(String::from_utf8(self.take(len)?.to_vec())?));
+                }
+                kind => return Err(format!("unknown tuple column kind {}", kind as char).into()),
+            }
+        }
+        Ok(tuple)
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Message, Error> {
+    let mut reader = Reader { data };
+    let message = match reader.u8()? {
+        b'B' => Message::Begin,
+        b'C' => {
+            reader.u8()?;
+            reader.u64()?;
+            Message::Commit {
+                end_lsn: reader.u64()?,
+            }
+        }
+        b'R' => {
+            let id = reader.u32()?;
+            let namespace = reader.string()?;
+            let name = reader.string()?;
+            reader.u8()?;
+            let count = reader.u16()?;
+            let mut columns = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                reader.u8()?;
+                columns.push(reader.string()?);
+                reader.u32()?;
+                reader.u32()?;
+            }
+            Message::Relation(Relation {
+                id,
+                namespace,
+                name,
+                columns,
+            })
+        }
+        b'I' => {
+            let relation = reader.u32()?;
+            reader.u8()?;
+            Message::Insert {
+                relation,
+                new: reader.tuple()?,
+            }
+        }
+        b'U' => {
+            let relation = reader.u32()?;
+            let mut kind = reader.u8()?;
+            let mut old = None;
+            if kind == b'K' || kind == b'O' {
+                old = Some(reader.tuple()?);
+                kind = reader.u8()?;
+            }
+            if kind != b'N' {
+                return Err("update message without a new tuple".into());
+            }
+            Message::Update {
+                relation,
+                old,
+                new: reader.tuple()?,
+            }
+        }
+        b'D' => {
+            let relation = reader.u32()?;
+            reader.u8()?;
+            Message::Delete {
+                relation,
+                old: reader.tuple()?,
+            }
+        }
+        b'T' => {
+            let count = reader.u32()?;
+            reader.u8()?;
+            let mut relations = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                relations.push(reader.u32()?);
+            }
+            Message::Truncate { relations }
+        }
+        _ => Message::Other,
+    };
+    Ok(message)
+}
+
+pub fn format_lsn(lsn: u64) -> String {
+    format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Vec<u8> {
+        [value.as_bytes(), &[0]].concat()
+    }
+
+    fn tuple(columns: &[Option<&str>]) -> Vec<u8> {
+        let mut data = (columns.len() as u16).to_be_bytes().to_vec();
+        for column in columns {
+            match column {
+                Some(value) => {
+                    data.push(b't');
+                    data.extend((value.len() as u32).to_be_bytes());
+                    data.extend(value.as_bytes());
+                }
+                None => data.push(b'n'),
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn relations_name_their_columns() {
+        let mut data = vec![b'R'];
+        data.extend(16384u32.to_be_bytes());
+        data.extend(string("public"));
+        data.extend(string("users"));
+        data.push(b'd');
+        data.extend(2u16.to_be_bytes());
+        for column in ["id", "email"] {
+            data.push(1);
+            data.extend(string(column));
+            data.extend([0; 8]);
+        }
+        let Message::Relation(relation) = decode(&data).unwrap() else {
+            panic!("relation is not decoded");
+        };
+        assert_eq!(relation.id, 16384);
+        assert_eq!(relation.namespace, "public");
+        assert_eq!(relation.name, "users");
+        assert_eq!(relation.columns, ["id", "email"]);
+
+        let row = vec![Some("1".to_string()), None];
+        assert_eq!(relation.value(&row, "id"), Some("1"));
+        assert_eq!(relation.value(&row, "email"), None);
+        assert_eq!(relation.value(&row, "name"), None);
+    }
+
+    #[test]
+    fn row_changes_carry_their_tuples() {
+        let mut data = vec![b'I'];
+        data.extend(7u32.to_be_bytes());
+        data.push(b'N');
+        data.extend(tuple(&[Some("1"), None]));
+        let Message::Insert { relation, new } = decode(&data).unwrap() else {
+            panic!("insert is not decoded");
+        };
+        assert_eq!((relation, new), (7, vec![Some("1".to_string()), None]));
+
+        let mut data = vec![b'U'];
+        data.extend(7u32.to_be_bytes());
+        data.push(b'K');
+        data.extend(tuple(&[Some("1")]));
+        data.push(b'N');
+        data.extend(tuple(&[Some("2")]));
+        let Message::Update { old, new, .. } = decode(&data).unwrap() else {
+            panic!("update is not decoded");
+        };
+        assert_eq!(old, Some(vec![Some("1".to_string())]));
+        assert_eq!(new, vec![Some("2".to_string())]);
+
+        let mut data = vec![b'D'];
+        data.extend(7u32.to_be_bytes());
+        data.push(b'K');
+        data.extend(tuple(&[Some("1")]));
+        let Message::Delete { old, .. } = decode(&data).unwrap() else {
+            panic!("delete is not decoded");
+        };
+        assert_eq!(old, vec![Some("1".to_string())]);
+
+        let mut data = vec![b'T'];
+        data.extend(2u32.to_be_bytes());
+        data.push(0);
+        data.extend(7u32.to_be_bytes());
+        data.extend(8u32.to_be_bytes());
+        let Message::Truncate { relations } = decode(&data).unwrap() else {
+            panic!("truncate is not decoded");
+        };
+        assert_eq!(relations, [7, 8]);
+    }
+
+    #[test]
+    fn commits_end_at_their_lsn() {
+        let mut data = vec![b'C', 0];
+        data.extend(1u64.to_be_bytes());
+        data.extend(0x1_0000_00A0u64.to_be_bytes());
+        data.extend(0u64.to_be_bytes());
+        let Message::Commit { end_lsn } = decode(&data).unwrap() else {
+            panic!("commit is not decoded");
+        };
+        assert_eq!(format_lsn(end_lsn), "1/A0");
+        assert!(matches!(decode(b"B"), Ok(Message::Begin)));
+        assert!(matches!(decode(b"O"), Ok(Message::Other)));
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[b'I', 0, 0]).is_err());
+        let mut data = vec![b'R'];
+        data.extend(1u32.to_be_bytes());
+        data.extend(b"public");
+        assert!(decode(&data).is_err());
+
+        let mut data = vec![b'U'];
+        data.extend(7u32.to_be_bytes());
+        data.push(b'K');
+        data.extend(tuple(&[Some("1")]));
+        data.push(b'X');
+        assert!(decode(&data).is_err());
+
+        let mut data = vec![b'I'];
+        data.extend(7u32.to_be_bytes());
+        data.push(b'N');
+        data.extend(1u16.to_be_bytes());
+        data.push(b'b');
+        let error = decode(&data).err().unwrap();
+        assert_eq!(error.to_string(), "unknown tuple column kind b");
+    }
+}
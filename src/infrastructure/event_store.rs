@@ -0,0 +1,666 @@
+use crate::domain::dto::Scan;
+use crate::domain::interfaces;
+use crate::domain::interfaces::{Aggregate, CheckpointStore, Identifiable, MessageBroker};
+use crate::domain::models::{Change, Envelope, Version};
+use crate::domain::principal;
+use crate::infrastructure::upgrade::{upgrade, upgrade_price};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Pool};
+use log::error;
+use serde_json::{json, Value};
+use std::error;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::ToSql;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+type Broker = Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Error> + Send + Sync>;
+type Checkpoints = Arc<dyn CheckpointStore<Error = Error> + Send + Sync>;
+
+/// Stream types and the topics their events are relayed to.
+const STREAMS: &[(&str, &str)] = &[("user", "user-events"), ("product", "product-events")];
+
+/// Another writer appended to the stream between reading and appending.
+#[derive(Debug)]
+pub struct ConcurrencyConflict;
+
+impl fmt::Display for ConcurrencyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entity was modified concurrently")
+    }
+}
+
+impl error::Error for ConcurrencyConflict {}
+
+/// Event sourced storage: every change is appended to the `Events` stream of
+/// the aggregate and the current state is the fold of those events, starting
+/// from the latest snapshot.
+pub struct EventStore {
+    pool: Pool,
+    snapshot_every: i64,
+}
+
+impl EventStore {
+    pub fn new(pool: Pool, snapshot_every: i64) -> EventStore {
+        EventStore {
+            pool,
+            snapshot_every,
+        }
+    }
+
+    /// Folds the stream into its current state and returns it with the
+    /// sequence number of the last event.
+    async fn load<T: Aggregate>(
+        client: &impl GenericClient,
+        id: &str,
+    ) -> Result<(Option<T>, i64), Error> {
+        // Snapshots taken before entities had timestamps get those of the
+        // events they were taken at, those taken at a delete once kept the
+        // deleted state.
+        let snapshot = client
+            .query_opt(
+                "SELECT sequence, Snapshots.data, event.recorded_at, event.event_type, (
+                    SELECT max(recorded_at) FROM Events created
+                    WHERE created.stream_type = Snapshots.stream_type
+                    AND created.stream_id = Snapshots.stream_id
+                    AND created.event_type = 'created'
+                    AND created.sequence <= Snapshots.sequence
+                ) AS created_at
+                FROM Snapshots JOIN Events event USING (stream_type, stream_id, sequence)
+                WHERE stream_type = $1 AND stream_id = $2",
+                &[&T::STREAM, &id],
+            )
+            .await?;
+        let (mut state, mut sequence, mut created_at) = match snapshot {
+            Some(row) => {
+                let data: Option<Value> = row.get("data");
+                let data = data.filter(|_| Self::live(row.get("event_type")));
+                let recorded_at: DateTime<Utc> = row.get("recorded_at");
+                let created_at: Option<DateTime<Utc>> = row.get("created_at");
+                let state: Option<T> = data
+                    .map(|data| upgrade(data, created_at.unwrap_or(recorded_at), recorded_at))
+                    .map(serde_json::from_value)
+                    .transpose()?;
+                let created_at = state.as_ref().and_then(created);
+                (state, row.get("sequence"), created_at)
+            }
+            None => (None, 0, None),
+        };
+        let events = client
+            .query(
+                "SELECT sequence, event_type, data, recorded_at FROM Events
+                WHERE stream_type = $1 AND stream_id = $2 AND sequence > $3
+                ORDER BY sequence",
+                &[&T::STREAM, &id, &sequence],
+            )
+            .await?;
+        for event in &events {
+            sequence = event.get("sequence");
+            state = Self::apply(
+                event.get("event_type"),
+                event.get("data"),
+                event.get("recorded_at"),
+                &mut created_at,
+            )?;
+        }
+        Ok((state, sequence))
+    }
+
+    /// State of the aggregate after an event. `created_at` is when the
+    /// entity of the stream was created, for states recorded without it.
+    fn apply<T: Aggregate>(
+        event_type: &str,
+        data: Option<Value>,
+        recorded_at: DateTime<Utc>,
+        created_at: &mut Option<DateTime<Utc>>,
+    ) -> Result<Option<T>, Error> {
+        match event_type {
+            "created" | "updated" | "restored" => {
+                if event_type == "created" {
+                    *created_at = None;
+                }
+                let data = data.ok_or("event without data")?;
+                let data = upgrade(data, created_at.unwrap_or(recorded_at), recorded_at);
+                let state: T = serde_json::from_value(data)?;
+                *created_at = created(&state);
+                Ok(Some(state))
+            }
+            "deleted" | "purged" => Ok(None),
+            other => Err(format!("unknown event type: {other}").into()),
+        }
+    }
+
+    /// Whether the entity exists after an event of `event_type`.
+    fn live(event_type: &str) -> bool {
+        !matches!(event_type, "deleted" | "purged")
+    }
+
+    /// Name of the change an event records, as used in messages and history.
+    fn operation(event_type: &str) -> &str {
+        match event_type {
+            "created" => "create",
+            "updated" => "update",
+            "deleted" => "delete",
+            "restored" => "restore",
+            "purged" => "purge",
+            other => other,
+        }
+    }
+
+    async fn append<T: Aggregate>(
+        &self,
+        id: &str,
+        expected: i64,
+        event_type: &str,
+        state: Option<&T>,
+    ) -> Result<(), Error> {
+        let mut connection = self.pool.get().await?;
+        let transaction = connection.transaction().await?;
+        let data = state.map(serde_json::to_value).transpose()?;
+        let sequence = expected + 1;
+        let result = transaction
+            .execute(
+                "INSERT INTO Events (stream_type, stream_id, sequence, event_type, data, principal)
+                VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &T::STREAM,
+                    &id,
+                    &sequence,
+                    &event_type,
+                    &data,
+                    &principal::current(),
+                ],
+            )
+            .await;
+        if let Err(err) = result {
+            if err.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                return Err(ConcurrencyConflict.into());
+            }
+            return Err(err.into());
+        }
+        if sequence % self.snapshot_every == 0 {
+            let data = data.filter(|_| Self::live(event_type));
+            transaction
+                .execute(
+                    "INSERT INTO Snapshots (stream_type, stream_id, sequence, data)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (stream_type, stream_id)
+                    DO UPDATE SET sequence = $3, data = $4",
+                    &[&T::STREAM, &id, &sequence, &data],
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: Aggregate + Send + Sync + 'static> interfaces::Database<T> for EventStore {
+    type Error = Error;
+
+    async fn add(&self, mut item: T) -> Result<T, Self::Error> {
+        let id = item.id();
+        let connection = self.pool.get().await?;
+        let (state, sequence) = Self::load::<T>(&connection, &id).await?;
+        if state.is_some() {
+            return Err(io::Error::from(ErrorKind::AlreadyExists).into());
+        }
+        let now = Utc::now();
+        item.set_timestamps(now, now);
+        self.append(&id, sequence, "created", Some(&item)).await?;
+        Ok(item)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, Self::Error> {
+        let id = id.to_string();
+        let connection = self.pool.get().await?;
+        let (state, sequence) = Self::load::<T>(&connection, &id).await?;
+        if state.is_none() {
+            return Ok(false);
+        }
+        self.append(&id, sequence, "deleted", state.as_ref())
+            .await?;
+        Ok(true)
+    }
+
+    async fn update(&self, mut item: T) -> Result<Option<T>, Self::Error> {
+        let id = item.id();
+        let connection = self.pool.get().await?;
+        let (state, sequence) = Self::load::<T>(&connection, &id).await?;
+        let Some(state) = state else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        let created_at = state.timestamps().map_or(now, |(created_at, _)| created_at);
+        item.set_timestamps(created_at, now);
+        self.append(&id, sequence, "updated", Some(&item)).await?;
+        Ok(Some(item))
+    }
+
+    async fn upsert(&self, mut item: T) -> Result<Option<Change<T>>, Self::Error> {
+        let id = item.id();
+        let connection = self.pool.get().await?;
+        let (state, sequence) = Self::load::<T>(&connection, &id).await?;
+        let now = Utc::now();
+        let Some(state) = state else {
+            item.set_timestamps(now, now);
+            self.append(&id, sequence, "created", Some(&item)).await?;
+            return Ok(Some(Change::Created(item)));
+        };
+        let created_at = state.timestamps().map_or(now, |(created_at, _)| created_at);
+        item.set_timestamps(created_at, now);
+        self.append(&id, sequence, "updated", Some(&item)).await?;
+        Ok(Some(Change::Updated(item)))
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<T>, Self::Error> {
+        let connection = self.pool.get().await?;
+        let (state, _) = Self::load(&connection, &id.to_string()).await?;
+        Ok(state)
+    }
+
+    async fn scan(&self, scan: &Scan) -> Result<Vec<T>, Self::Error> {
+        let connection = self.pool.get().await?;
+        let after = scan.after.map(|id| id.to_string());
+        let ids: Option<Vec<String>> = scan
+            .ids
+            .as_ref()
+            .map(|ids| ids.iter().map(|id| id.to_string()).collect());
+        let limit = scan.limit as i64;
+        let params: [&(dyn ToSql + Sync); 5] =
+            [&T::STREAM, &after, &ids, &scan.updated_since, &limit];
+        let rows = connection
+            .query(
+                "SELECT stream_id FROM Events
+                WHERE stream_type = $1
+                AND ($2::TEXT IS NULL OR stream_id > $2)
+                AND ($3::TEXT[] IS NULL OR stream_id = ANY($3))
+                GROUP BY stream_id
+                HAVING (array_agg(event_type ORDER BY sequence DESC))[1]
+                    NOT IN ('deleted', 'purged')
+                AND ($4::TIMESTAMPTZ IS NULL OR max(recorded_at) >= $4)
+                ORDER BY stream_id LIMIT $5",
+                &params,
+            )
+            .await?;
+        let mut items = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let (state, _) = Self::load(&connection, row.get("stream_id")).await?;
+            items.extend(state);
+        }
+        Ok(items)
+    }
+
+    async fn get_as_of(&self, id: Uuid, as_of: DateTime<Utc>) -> Result<Option<T>, Self::Error> {
+        let connection = self.pool.get().await?;
+        let events = connection
+            .query(
+                "SELECT event_type, data, recorded_at FROM Events
+                WHERE stream_type = $1 AND stream_id = $2 AND recorded_at <= $3
+                ORDER BY sequence",
+                &[&T::STREAM, &id.to_string(), &as_of],
+            )
+            .await?;
+        let mut state = None;
+        let mut created_at = None;
+        for event in &events {
+            state = Self::apply(
+                event.get("event_type"),
+                event.get("data"),
+                event.get("recorded_at"),
+                &mut created_at,
+            )?;
+        }
+        Ok(state)
+    }
+
+    async fn history(&self, id: Uuid) -> Result<Vec<Version<T>>, Self::Error> {
+        let connection = self.pool.get().await?;
+        let events = connection
+            .query(
+                "SELECT sequence, event_type, data, principal, recorded_at,
+                lead(recorded_at) OVER (ORDER BY sequence) AS valid_to
+                FROM Events WHERE stream_type = $1 AND stream_id = $2
+                ORDER BY sequence",
+                &[&T::STREAM, &id.to_string()],
+            )
+            .await?;
+        let mut created_at = None;
+        events
+            .iter()
+            .map(|event| {
+                let event_type: &str = event.get("event_type");
+                Ok(Version {
+                    version: event.get("sequence"),
+                    operation: Self::operation(event_type).to_string(),
+                    principal: event.get("principal"),
+                    valid_from: event.get("recorded_at"),
+                    valid_to: event.get("valid_to"),
+                    state: Self::apply(
+                        event_type,
+                        event.get("data"),
+                        event.get("recorded_at"),
+                        &mut created_at,
+                    )?,
+                })
+            })
+            .collect()
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<bool, Self::Error> {
+        let id = id.to_string();
+        let connection = self.pool.get().await?;
+        let last = connection
+            .query_opt(
+                "SELECT sequence, event_type, data, recorded_at, (
+                    SELECT max(recorded_at) FROM Events created
+                    WHERE created.stream_type = $1 AND created.stream_id = $2
+                    AND created.event_type = 'created'
+                ) AS created_at
+                FROM Events
+                WHERE stream_type = $1 AND stream_id = $2
+                ORDER BY sequence DESC LIMIT 1",
+                &[&T::STREAM, &id],
+            )
+            .await?;
+        let Some(last) = last else {
+            return Ok(false);
+        };
+        let data: Option<Value> = last.get("data");
+        let (event_type, Some(data)) = (last.get::<_, &str>("event_type"), data) else {
+            return Ok(false);
+        };
+        if event_type != "deleted" {
+            return Ok(false);
+        }
+        let recorded_at = last.get("recorded_at");
+        let created_at = last
+            .get::<_, Option<_>>("created_at")
+            .unwrap_or(recorded_at);
+        let mut state: T = serde_json::from_value(upgrade(data, created_at, recorded_at))?;
+        let now = Utc::now();
+        let created_at = state.timestamps().map_or(now, |(created_at, _)| created_at);
+        state.set_timestamps(created_at, now);
+        self.append(&id, last.get("sequence"), "restored", Some(&state))
+            .await?;
+        Ok(true)
+    }
+
+    /// Appends a `purged` event to the streams deleted before
+    /// `deleted_before`. The events themselves are immutable, so the purged
+    /// stream can no longer be read or restored but stays in the log.
+    async fn purge(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Uuid>, Self::Error> {
+        let connection = self.pool.get().await?;
+        let streams = connection
+            .query(
+                "SELECT stream_id, max(sequence) AS sequence FROM Events
+                WHERE stream_type = $1
+                GROUP BY stream_id
+                HAVING (array_agg(event_type ORDER BY sequence DESC))[1] = 'deleted'
+                AND max(recorded_at) < $2
+                LIMIT $3",
+                &[&T::STREAM, &deleted_before, &(limit as i64)],
+            )
+            .await?;
+        let mut purged = Vec::with_capacity(streams.len());
+        for stream in &streams {
+            let id: &str = stream.get("stream_id");
+            self.append::<T>(id, stream.get("sequence"), "purged", None)
+                .await?;
+            purged.push(Uuid::parse_str(id)?);
+        }
+        Ok(purged)
+    }
+}
+
+/// Creation time of an entity.
+fn created<T: Identifiable>(state: &T) -> Option<DateTime<Utc>> {
+    state.timestamps().map(|(created_at, _)| created_at)
+}
+
+/// Position of an event in commit order: the id of the transaction that
+/// recorded it and its position within the log.
+#[derive(Clone, Default, PartialEq)]
+pub struct LogPosition {
+    pub transaction_id: u64,
+    pub position: i64,
+}
+
+impl fmt::Display for LogPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.transaction_id, self.position)
+    }
+}
+
+impl FromStr for LogPosition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (transaction_id, position) = s
+            .split_once(':')
+            .ok_or_else(|| format!("malformed log position: {s}"))?;
+        Ok(LogPosition {
+            transaction_id: transaction_id.parse()?,
+            position: position.parse()?,
+        })
+    }
+}
+
+pub struct RecordedEvent {
+    pub log_position: LogPosition,
+    pub stream_type: String,
+    pub stream_id: String,
+    pub event_type: String,
+    pub data: Option<Value>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl EventStore {
+    /// Reads events after `after` in commit order. Only transactions older
+    /// than every running one are returned, so an event that commits late
+    /// with a lower position is never skipped by a reader that checkpoints
+    /// the last position it has seen.
+    pub async fn read_committed(
+        client: &impl GenericClient,
+        after: &LogPosition,
+        limit: i64,
+    ) -> Result<Vec<RecordedEvent>, Error> {
+        Self::read_log(client, "Events", after, limit).await
+    }
+
+    /// `read_committed` over `log`, a relation with the columns of `Events`.
+    pub(crate) async fn read_log(
+        client: &impl GenericClient,
+        log: &str,
+        after: &LogPosition,
+        limit: i64,
+    ) -> Result<Vec<RecordedEvent>, Error> {
+        let transaction_id = after.transaction_id.to_string();
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT transaction_id::TEXT AS transaction_id, position, stream_type,
+                    stream_id, event_type, data, recorded_at
+                    FROM {log}
+                    WHERE (transaction_id, position) > ($1::TEXT::XID8, $2)
+                    AND transaction_id < pg_snapshot_xmin(pg_current_snapshot())
+                    ORDER BY transaction_id, position LIMIT $3"
+                ),
+                &[&transaction_id, &after.position, &limit],
+            )
+            .await?;
+        let mut events = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let recorded_at = row.get("recorded_at");
+            let data: Option<Value> = row.get("data");
+            events.push(RecordedEvent {
+                log_position: LogPosition {
+                    transaction_id: row.get::<_, &str>("transaction_id").parse()?,
+                    position: row.get("position"),
+                },
+                stream_type: row.get("stream_type"),
+                stream_id: row.get("stream_id"),
+                event_type: row.get("event_type"),
+                data: data.map(|data| upgrade(data, recorded_at, recorded_at)),
+                recorded_at,
+            });
+        }
+        Ok(events)
+    }
+
+    /// Number of events of `log` recorded after `after`, including those of
+    /// transactions that are not yet visible to `read_committed`.
+    pub async fn count_after(
+        client: &impl GenericClient,
+        log: &str,
+        after: &LogPosition,
+    ) -> Result<i64, Error> {
+        let transaction_id = after.transaction_id.to_string();
+        let row = client
+            .query_one(
+                &format!(
+                    "SELECT count(*) AS pending FROM {log}
+                    WHERE (transaction_id, position) > ($1::TEXT::XID8, $2)"
+                ),
+                &[&transaction_id, &after.position],
+            )
+            .await?;
+        Ok(row.get("pending"))
+    }
+}
+
+/// Publishes events from the event log in commit order.
+pub struct EventRelay {
+    pool: Pool,
+    broker: Broker,
+    checkpoints: Checkpoints,
+    batch_size: i64,
+}
+
+impl EventRelay {
+    const CHECKPOINT: &'static str = "event-relay";
+
+    pub fn new(
+        pool: Pool,
+        broker: Broker,
+        checkpoints: Checkpoints,
+        batch_size: i64,
+    ) -> EventRelay {
+        EventRelay {
+            pool,
+            broker,
+            checkpoints,
+            batch_size,
+        }
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let relay = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match relay.relay().await {
+                    Ok(count) if count as i64 == relay.batch_size => continue,
+                    Ok(_) => {}
+                    Err(err) => error!("Event relay failed: {}", err),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    async fn relay(&self) -> Result<usize, Error> {
+        let after = match self.checkpoints.load(Self::CHECKPOINT).await? {
+            Some(checkpoint) => checkpoint.parse()?,
+            None => LogPosition::default(),
+        };
+        let connection = self.pool.get().await?;
+        let events = EventStore::read_committed(&connection, &after, self.batch_size).await?;
+        for event in &events {
+            let action = EventStore::operation(&event.event_type);
+            if let Some((_, topic)) = STREAMS
+                .iter()
+                .find(|(stream, _)| *stream == event.stream_type)
+            {
+                let id = Uuid::parse_str(&event.stream_id)?;
+                let mut envelope = Envelope::new(topic, action, &id);
+                envelope.created_at = Self::timestamp(event, "created_at");
+                envelope.updated_at = Self::timestamp(event, "updated_at");
+                if let Some(price) = event.data.as_ref().and_then(|data| data.get("price")) {
+                    envelope.data = Some(json!({"price": upgrade_price(price.clone())}));
+                }
+                self.broker.publish(envelope).await?;
+            }
+            self.checkpoints
+                .save(Self::CHECKPOINT, &event.log_position.to_string())
+                .await?;
+        }
+        Ok(events.len())
+    }
+
+    fn timestamp(event: &RecordedEvent, field: &str) -> Option<DateTime<Utc>> {
+        let value = event.data.as_ref()?.get(field)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::interfaces::Database;
+    use crate::domain::models::User;
+    use crate::test_support::{postgres, user};
+
+    async fn assert_deleted(store: &EventStore, user: &User) {
+        let scan = Scan {
+            ids: Some(vec![user.id]),
+            updated_since: None,
+            after: None,
+            limit: 10,
+        };
+        assert!(Database::<User>::get(store, user.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store.update(user.clone()).await.unwrap().is_none());
+        assert!(Database::<User>::scan(store, &scan)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!Database::<User>::delete(store, user.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn deletes_stay_deleted_when_they_are_snapshotted() {
+        let store = EventStore::new(postgres().await.pool(), 2);
+        let user = store.add(user()).await.unwrap();
+        assert!(Database::<User>::delete(&store, user.id).await.unwrap());
+        assert_deleted(&store, &user).await;
+
+        // Snapshots taken at a delete used to keep the deleted state.
+        let connection = store.pool.get().await.unwrap();
+        connection
+            .execute(
+                "UPDATE Snapshots SET data = event.data FROM Events event
+                WHERE event.stream_type = Snapshots.stream_type
+                AND event.stream_id = Snapshots.stream_id
+                AND event.sequence = Snapshots.sequence AND Snapshots.stream_id = $1",
+                &[&user.id.to_string()],
+            )
+            .await
+            .unwrap();
+        assert_deleted(&store, &user).await;
+    }
+}
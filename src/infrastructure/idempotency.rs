@@ -0,0 +1,242 @@
+use deadpool_postgres::Pool;
+use log::{error, info};
+use std::error;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// How long a claimed key stays with its request before another request with
+/// the key may run instead.
+const DEFAULT_LEASE: Duration = Duration::from_secs(60);
+/// How often a request checks on a key claimed by another one.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Responses to requests made with an idempotency key, kept for `ttl`.
+pub struct IdempotencyStore {
+    pool: Pool,
+    ttl: Duration,
+    lease: Duration,
+}
+
+pub enum Claim {
+    /// The key is new, the request runs and its response is stored.
+    Acquired(Box<PendingResponse>),
+    /// The key was used for the same request before.
+    Replay { status: u16, body: Vec<u8> },
+    /// The key was used for a different request.
+    Mismatch,
+}
+
+impl IdempotencyStore {
+    pub fn new(pool: Pool, ttl: Duration) -> IdempotencyStore {
+        IdempotencyStore {
+            pool,
+            ttl,
+            lease: DEFAULT_LEASE,
+        }
+    }
+
+    /// Keeps a claimed key with its request for `lease`.
+    pub fn with_lease(mut self, lease: Duration) -> IdempotencyStore {
+        self.lease = lease;
+        self
+    }
+
+    /// Claims `key` within `scope` for a request with body `request`. A
+    /// request claiming a key that is still pending waits for its response,
+    /// or takes the key over once the lease of the other request lapses.
+    pub async fn claim(&self, scope: &str, key: &str, request: &[u8]) -> Result<Claim, Error> {
+        let lease = Uuid::new_v4();
+        loop {
+            // Each statement commits on its own, no connection is held while
+            // the request runs or while waiting for another one.
+            let connection = self.pool.get().await?;
+            connection
+                .execute(
+                    "DELETE FROM IdempotencyKeys
+                    WHERE scope = $1 AND key = $2 AND expires_at <= now()",
+                    &[&scope, &key],
+                )
+                .await?;
+            let inserted = connection
+                .execute(
+                    "INSERT INTO IdempotencyKeys
+                        (scope, key, fingerprint, expires_at, lease, leased_until)
+                    VALUES ($1, $2, sha256($3), now() + make_interval(secs => $4), $5,
+                        now() + make_interval(secs => $6))
+                    ON CONFLICT (scope, key) DO NOTHING",
+                    &[
+                        &scope,
+                        &key,
+                        &request,
+                        &self.ttl.as_secs_f64(),
+                        &lease,
+                        &self.lease.as_secs_f64(),
+                    ],
+                )
+                .await?;
+            if inserted == 1 {
+                return Ok(self.acquired(scope, key, lease));
+            }
+            let row = connection
+                .query_opt(
+                    "SELECT status, response, fingerprint = sha256($3) AS matches
+                    FROM IdempotencyKeys WHERE scope = $1 AND key = $2",
+                    &[&scope, &key, &request],
+                )
+                .await?;
+            // Released or expired in the meantime, claim it again.
+            let Some(row) = row else {
+                continue;
+            };
+            if !row.try_get::<_, bool>("matches")? {
+                return Ok(Claim::Mismatch);
+            }
+            if let Some(status) = row.try_get::<_, Option<i16>>("status")? {
+                return Ok(Claim::Replay {
+                    status: status as u16,
+                    body: row.try_get("response")?,
+                });
+            }
+            let taken = connection
+                .execute(
+                    "UPDATE IdempotencyKeys
+                    SET lease = $3, leased_until = now() + make_interval(secs => $4)
+                    WHERE scope = $1 AND key = $2 AND status IS NULL AND leased_until <= now()",
+                    &[&scope, &key, &lease, &self.lease.as_secs_f64()],
+                )
+                .await?;
+            if taken == 1 {
+                return Ok(self.acquired(scope, key, lease));
+            }
+            drop(connection);
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    fn acquired(&self, scope: &str, key: &str, lease: Uuid) -> Claim {
+        Claim::Acquired(Box::new(PendingResponse {
+            pool: self.pool.clone(),
+            scope: scope.to_string(),
+            key: key.to_string(),
+            lease,
+        }))
+    }
+
+    /// Removes the expired keys every `interval`.
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match store.expire().await {
+                    Ok(0) => {}
+                    Ok(expired) => info!("Removed {} expired idempotency keys", expired),
+                    Err(err) => error!("Failed to remove expired idempotency keys: {}", err),
+                }
+            }
+        });
+    }
+
+    async fn expire(&self) -> Result<u64, Error> {
+        let connection = self.pool.get().await?;
+        Ok(connection
+            .execute("DELETE FROM IdempotencyKeys WHERE expires_at <= now()", &[])
+            .await?)
+    }
+}
+
+/// A claimed key whose response is not stored yet. Dropped without
+/// `complete` or `release`, the key stays pending until its lease lapses.
+pub struct PendingResponse {
+    pool: Pool,
+    scope: String,
+    key: String,
+    lease: Uuid,
+}
+
+impl PendingResponse {
+    /// Stores the response for repeats of the request, unless the lease
+    /// lapsed and another request took the key over.
+    pub async fn complete(self, status: u16, body: &[u8]) -> Result<(), Error> {
+        let connection = self.pool.get().await?;
+        let stored = connection
+            .execute(
+                "UPDATE IdempotencyKeys
+                SET status = $4, response = $5, lease = NULL, leased_until = NULL
+                WHERE scope = $1 AND key = $2 AND lease = $3",
+                &[&self.scope, &self.key, &self.lease, &(status as i16), &body],
+            )
+            .await?;
+        if stored == 0 {
+            return Err("the lease of the idempotency key lapsed".into());
+        }
+        Ok(())
+    }
+
+    /// Forgets the key, so the request can be retried with it.
+    pub async fn release(self) -> Result<(), Error> {
+        let connection = self.pool.get().await?;
+        connection
+            .execute(
+                "DELETE FROM IdempotencyKeys WHERE scope = $1 AND key = $2 AND lease = $3",
+                &[&self.scope, &self.key, &self.lease],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::postgres;
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn idempotency_keys_replay_the_stored_response() {
+        let postgres = postgres().await;
+        let store = IdempotencyStore::new(postgres.pool(), Duration::from_secs(60));
+        let key = Uuid::new_v4().to_string();
+
+        let Claim::Acquired(pending) = store.claim("/users", &key, b"a").await.unwrap() else {
+            panic!("new key is not acquired");
+        };
+        pending.release().await.unwrap();
+        let Claim::Acquired(pending) = store.claim("/users", &key, b"a").await.unwrap() else {
+            panic!("released key is not acquired");
+        };
+        pending.complete(201, b"created").await.unwrap();
+
+        let Claim::Replay { status, body } = store.claim("/users", &key, b"a").await.unwrap()
+        else {
+            panic!("stored response is not replayed");
+        };
+        assert_eq!((status, body.as_slice()), (201, b"created".as_slice()));
+        let claim = store.claim("/users", &key, b"b").await.unwrap();
+        assert!(matches!(claim, Claim::Mismatch));
+        let claim = store.claim("/products", &key, b"b").await.unwrap();
+        assert!(matches!(claim, Claim::Acquired(_)));
+
+        // A pending key is taken over once its lease lapses, the response of
+        // the request that lost it is not stored.
+        let store = IdempotencyStore::new(postgres.pool(), Duration::from_secs(60))
+            .with_lease(Duration::ZERO);
+        let key = Uuid::new_v4().to_string();
+        let Claim::Acquired(lost) = store.claim("/users", &key, b"a").await.unwrap() else {
+            panic!("new key is not acquired");
+        };
+        let Claim::Acquired(pending) = store.claim("/users", &key, b"a").await.unwrap() else {
+            panic!("lapsed key is not taken over");
+        };
+        assert!(lost.complete(201, b"lost").await.is_err());
+        pending.complete(201, b"created").await.unwrap();
+        let Claim::Replay { body, .. } = store.claim("/users", &key, b"a").await.unwrap() else {
+            panic!("stored response is not replayed");
+        };
+        assert_eq!(body, b"created");
+    }
+}
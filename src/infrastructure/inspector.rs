@@ -0,0 +1,203 @@
+use crate::domain::models::Envelope;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::Message;
+use rdkafka::types::RDKafkaErrorCode;
+use rdkafka::{ClientConfig, Offset, TopicPartitionList};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Serialize)]
+pub struct PartitionInfo {
+    pub id: i32,
+    pub leader: i32,
+    pub low: i64,
+    pub high: i64,
+}
+
+#[derive(Serialize)]
+pub struct TopicInfo {
+    pub name: String,
+    pub partitions: Vec<PartitionInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct MessageFilter {
+    pub from_offset: Option<i64>,
+    /// Exclusive, defaults to the high watermark at the time of the request.
+    pub to_offset: Option<i64>,
+    /// Milliseconds since the epoch, used when `from_offset` is not given.
+    pub from_timestamp: Option<i64>,
+    pub key: Option<String>,
+    pub entity_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct InspectedMessage {
+    pub partition: i32,
+    pub offset: i64,
+    pub timestamp: Option<i64>,
+    pub key: Option<String>,
+    pub envelope: Option<Envelope>,
+    /// Raw payload, only set when it could not be decoded as an `Envelope`.
+    pub payload: Option<String>,
+}
+
+/// Read-only view of the topics mesgmon publishes to. Every fetch uses its own
+/// manually assigned consumer that never commits offsets, so browsing has no
+/// effect on any consumer group.
+pub struct TopicInspector {
+    config: ClientConfig,
+    metadata: BaseConsumer,
+}
+
+impl TopicInspector {
+    pub fn new(kafka_brokers: &str) -> Result<TopicInspector, Error> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", kafka_brokers)
+            .set("group.id", "mesgmon-inspector")
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("enable.partition.eof", "true");
+        let metadata = config.create()?;
+        Ok(TopicInspector { config, metadata })
+    }
+
+    pub async fn topics(self: &Arc<Self>) -> Result<Vec<TopicInfo>, Error> {
+        let inspector = self.clone();
+        tokio::task::spawn_blocking(move || inspector.fetch_topics()).await?
+    }
+
+    pub async fn messages(
+        self: &Arc<Self>,
+        topic: String,
+        partition: i32,
+        filter: MessageFilter,
+    ) -> Result<Vec<InspectedMessage>, Error> {
+        let inspector = self.clone();
+        tokio::task::spawn_blocking(move || inspector.fetch_messages(&topic, partition, &filter))
+            .await?
+    }
+
+    fn fetch_topics(&self) -> Result<Vec<TopicInfo>, Error> {
+        let metadata = self.metadata.fetch_metadata(None, TIMEOUT)?;
+        let mut topics = Vec::new();
+        for topic in metadata.topics() {
+            if topic.name().starts_with("__") {
+                continue;
+            }
+            let mut partitions = Vec::new();
+            for partition in topic.partitions() {
+                let (low, high) =
+                    self.metadata
+                        .fetch_watermarks(topic.name(), partition.id(), TIMEOUT)?;
+                partitions.push(PartitionInfo {
+                    id: partition.id(),
+                    leader: partition.leader(),
+                    low,
+                    high,
+                });
+            }
+            topics.push(TopicInfo {
+                name: topic.name().to_string(),
+                partitions,
+            });
+        }
+        topics.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(topics)
+    }
+
+    fn fetch_messages(
+        &self,
+        topic: &str,
+        partition: i32,
+        filter: &MessageFilter,
+    ) -> Result<Vec<InspectedMessage>, Error> {
+        let (low, high) = match self.metadata.fetch_watermarks(topic, partition, TIMEOUT) {
+            Ok(watermarks) => watermarks,
+            Err(KafkaError::MetadataFetch(RDKafkaErrorCode::UnknownPartition))
+            | Err(KafkaError::MetadataFetch(RDKafkaErrorCode::UnknownTopicOrPartition)) => {
+                return Err(io::Error::from(ErrorKind::NotFound).into())
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let consumer: BaseConsumer = self.config.create()?;
+        let start = match (filter.from_offset, filter.from_timestamp) {
+            (Some(offset), _) => offset.max(low),
+            (None, Some(timestamp)) => {
+                let mut timestamps = TopicPartitionList::new();
+                timestamps.add_partition_offset(topic, partition, Offset::Offset(timestamp))?;
+                let offsets = consumer.offsets_for_times(timestamps, TIMEOUT)?;
+                match offsets
+                    .find_partition(topic, partition)
+                    .and_then(|element| element.offset().to_raw())
+                {
+                    Some(offset) if offset >= 0 => offset,
+                    _ => high,
+                }
+            }
+            (None, None) => low,
+        };
+        let end = filter.to_offset.unwrap_or(high).min(high);
+        let limit = filter.limit.unwrap_or(100).min(MAX_LIMIT);
+        let mut messages = Vec::new();
+        if start >= end {
+            return Ok(messages);
+        }
+
+        let mut assignment = TopicPartitionList::new();
+        assignment.add_partition_offset(topic, partition, Offset::Offset(start))?;
+        consumer.assign(&assignment)?;
+        let deadline = Instant::now() + TIMEOUT;
+        while messages.len() < limit && Instant::now() < deadline {
+            let message = match consumer.poll(Duration::from_millis(100)) {
+                None => continue,
+                Some(Err(KafkaError::PartitionEOF(_))) => break,
+                Some(Err(err)) => return Err(err.into()),
+                Some(Ok(message)) => message,
+            };
+            if message.offset() >= end {
+                break;
+            }
+            let key = message
+                .key()
+                .map(|key| String::from_utf8_lossy(key).into_owned());
+            let payload = message.payload().unwrap_or_default();
+            let envelope = serde_json::from_slice::<Envelope>(payload).ok();
+            let key_matches = filter.key.is_none() || filter.key == key;
+            let entity_matches = match (&filter.entity_id, &envelope) {
+                (None, _) => true,
+                (Some(entity_id), Some(envelope)) => &envelope.entity_id == entity_id,
+                (Some(_), None) => false,
+            };
+            if key_matches && entity_matches {
+                messages.push(InspectedMessage {
+                    partition: message.partition(),
+                    offset: message.offset(),
+                    timestamp: message.timestamp().to_millis(),
+                    key,
+                    payload: match envelope {
+                        Some(_) => None,
+                        None => Some(String::from_utf8_lossy(payload).into_owned()),
+                    },
+                    envelope,
+                });
+            }
+            if message.offset() + 1 >= end {
+                break;
+            }
+        }
+        Ok(messages)
+    }
+}
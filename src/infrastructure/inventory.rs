@@ -0,0 +1,203 @@
+use crate::domain::interfaces;
+use crate::domain::models::{Reservation, Stock};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use std::error;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+const STOCK: &str = "product_id, on_hand, reserved, available, low_stock_threshold";
+
+/// Inventory kept in the `Inventory` and `Reservations` tables. The stock is
+/// changed by conditional updates, so concurrent reservations serialize on
+/// the row of the product.
+pub struct PostgresInventory {
+    pool: Pool,
+}
+
+impl PostgresInventory {
+    pub fn new(pool: Pool) -> PostgresInventory {
+        PostgresInventory { pool }
+    }
+
+    /// Moves a pending reservation to `status` and returns its units to the
+    /// stock, or takes them out of it when confirmed.
+    async fn close(
+        &self,
+        id: Uuid,
+        status: &str,
+        adjustment: &str,
+    ) -> Result<Option<(Reservation, Stock)>, Error> {
+        let mut connection = self.pool.get().await?;
+        let transaction = connection.transaction().await?;
+        let Some(row) = transaction
+            .query_opt(
+                "UPDATE Reservations SET status = $2
+                WHERE id = $1 AND status = 'pending'
+                AND ($2 <> 'confirmed' OR expires_at > now())
+                RETURNING *",
+                &[&id, &status],
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let reservation = reservation(&row)?;
+        let row = transaction
+            .query_one(
+                &format!(
+                    "UPDATE Inventory SET {adjustment}, updated_at = now()
+                    WHERE product_id = $1 RETURNING {STOCK}"
+                ),
+                &[&reservation.product_id, &reservation.quantity],
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(Some((reservation, stock(&row)?)))
+    }
+}
+
+fn stock(row: &Row) -> Result<Stock, Error> {
+    Ok(Stock {
+        product_id: row.try_get("product_id")?,
+        on_hand: row.try_get("on_hand")?,
+        reserved: row.try_get("reserved")?,
+        available: row.try_get("available")?,
+        low_stock_threshold: row.try_get("low_stock_threshold")?,
+    })
+}
+
+fn reservation(row: &Row) -> Result<Reservation, Error> {
+    Ok(Reservation {
+        id: row.try_get("id")?,
+        product_id: row.try_get("product_id")?,
+        quantity: row.try_get("quantity")?,
+        status: row.try_get::<_, &str>("status")?.parse()?,
+        expires_at: row.try_get("expires_at")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[async_trait]
+impl interfaces::Inventory for PostgresInventory {
+    type Error = Error;
+
+    async fn stock(&self, product_id: Uuid) -> Result<Option<Stock>, Error> {
+        let connection = self.pool.get().await?;
+        let row = connection
+            .query_opt(
+                &format!("SELECT {STOCK} FROM Inventory WHERE product_id = $1"),
+                &[&product_id],
+            )
+            .await?;
+        row.as_ref().map(stock).transpose()
+    }
+
+    async fn set_stock(
+        &self,
+        product_id: Uuid,
+        on_hand: i64,
+        low_stock_threshold: i64,
+    ) -> Result<Option<Stock>, Error> {
+        let connection = self.pool.get().await?;
+        let row = connection
+            .query_opt(
+                &format!(
+                    "INSERT INTO Inventory (product_id, on_hand, low_stock_threshold)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (product_id) DO UPDATE
+                    SET on_hand = $2, low_stock_threshold = $3, updated_at = now()
+                    WHERE Inventory.reserved <= $2
+                    RETURNING {STOCK}"
+                ),
+                &[&product_id, &on_hand, &low_stock_threshold],
+            )
+            .await?;
+        row.as_ref().map(stock).transpose()
+    }
+
+    async fn reservation(&self, id: Uuid) -> Result<Option<Reservation>, Error> {
+        let connection = self.pool.get().await?;
+        let row = connection
+            .query_opt("SELECT * FROM Reservations WHERE id = $1", &[&id])
+            .await?;
+        row.as_ref().map(reservation).transpose()
+    }
+
+    async fn reserve(
+        &self,
+        product_id: Uuid,
+        quantity: i64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Option<(Reservation, Stock)>, Error> {
+        let mut connection = self.pool.get().await?;
+        let transaction = connection.transaction().await?;
+        let Some(row) = transaction
+            .query_opt(
+                &format!(
+                    "UPDATE Inventory SET reserved = reserved + $2, updated_at = now()
+                    WHERE product_id = $1 AND available >= $2
+                    RETURNING {STOCK}"
+                ),
+                &[&product_id, &quantity],
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        let stock = stock(&row)?;
+        let row = transaction
+            .query_one(
+                "INSERT INTO Reservations (id, product_id, quantity, expires_at)
+                VALUES ($1, $2, $3, $4) RETURNING *",
+                &[&Uuid::new_v4(), &product_id, &quantity, &expires_at],
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(Some((reservation(&row)?, stock)))
+    }
+
+    async fn confirm(&self, id: Uuid) -> Result<Option<(Reservation, Stock)>, Error> {
+        self.close(
+            id,
+            "confirmed",
+            "on_hand = on_hand - $2, reserved = reserved - $2",
+        )
+        .await
+    }
+
+    async fn release(&self, id: Uuid) -> Result<Option<(Reservation, Stock)>, Error> {
+        self.close(id, "released", "reserved = reserved - $2").await
+    }
+
+    async fn expire(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<Reservation>, Error> {
+        let connection = self.pool.get().await?;
+        let rows = connection
+            .query(
+                "WITH expired AS (
+                    UPDATE Reservations SET status = 'expired'
+                    WHERE id IN (
+                        SELECT id FROM Reservations
+                        WHERE status = 'pending' AND expires_at <= $1
+                        ORDER BY expires_at LIMIT $2
+                        FOR UPDATE SKIP LOCKED
+                    )
+                    RETURNING *
+                ), returned AS (
+                    UPDATE Inventory SET reserved = reserved - totals.quantity, updated_at = now()
+                    FROM (
+                        SELECT product_id, sum(quantity)::BIGINT AS quantity
+                        FROM expired GROUP BY product_id
+                    ) totals
+                    WHERE Inventory.product_id = totals.product_id
+                )
+                SELECT * FROM expired",
+                &[&now, &(limit as i64)],
+            )
+            .await?;
+        rows.iter().map(reservation).collect()
+    }
+}
@@ -1,22 +1,43 @@
 use crate::domain::interfaces;
 use crate::domain::interfaces::Identifiable;
+use crate::domain::models::Envelope;
+use crate::infrastructure::kafka_metrics::{KafkaMetrics, StatsContext};
 use axum::async_trait;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use futures_util::future::join_all;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::ClientConfig;
-use std::collections::HashMap;
 use std::error::Error;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct Kafka {
-    producer: FutureProducer,
+    producer: FutureProducer<StatsContext>,
+    metrics: Arc<KafkaMetrics>,
 }
 
 impl Kafka {
-    pub fn new(kafka_brokers: &str) -> Result<Kafka, Box<dyn Error + Send + Sync>> {
-        let producer: FutureProducer = ClientConfig::new()
+    pub fn new(
+        kafka_brokers: &str,
+        statistics_interval: Duration,
+    ) -> Result<Kafka, Box<dyn Error + Send + Sync>> {
+        let metrics = Arc::new(KafkaMetrics::default());
+        let producer: FutureProducer<StatsContext> = ClientConfig::new()
             .set("bootstrap.servers", kafka_brokers)
-            .create()?;
-        Ok(Kafka { producer })
+            .set(
+                "statistics.interval.ms",
+                statistics_interval.as_millis().to_string(),
+            )
+            .create_with_context(StatsContext::new(metrics.clone()))?;
+        Ok(Kafka { producer, metrics })
+    }
+
+    pub fn metrics(&self) -> Arc<KafkaMetrics> {
+        self.metrics.clone()
+    }
+
+    pub fn flush(&self, timeout: Duration) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.producer.flush(timeout)?;
+        Ok(())
     }
 }
 
@@ -25,23 +46,37 @@ impl<M: Identifiable + Sync + ?Sized> interfaces::MessageBroker<M> for Kafka {
     type Error = Box<dyn Error + Send + Sync>;
 
     async fn send(&self, topic: &str, action: &str, message: &M) -> Result<(), Self::Error> {
-        let payload = HashMap::from([
-            ("topic", topic.to_string()),
-            ("action", action.to_string()),
-            ("entity_id", message.id()),
-        ]);
+        interfaces::MessageBroker::<M>::publish(self, Envelope::new(topic, action, message)).await
+    }
+
+    async fn publish(&self, envelope: Envelope) -> Result<(), Self::Error> {
+        let started = Instant::now();
         let result = self
             .producer
             .send(
-                FutureRecord::to(topic)
-                    .payload(&serde_json::to_vec(&payload)?)
-                    .key(action),
+                FutureRecord::to(&envelope.topic)
+                    .payload(&serde_json::to_vec(&envelope)?)
+                    .key(&envelope.action),
                 Duration::from_secs(1),
             )
             .await;
+        self.metrics.record_delivery(
+            &envelope.topic,
+            &envelope.action,
+            result.as_ref().ok().map(|(partition, _)| *partition),
+            started.elapsed(),
+        );
         if let Err(err) = result {
             return Err(err.0.into());
         }
         Ok(())
     }
+
+    /// Hands the whole batch to the producer before waiting for deliveries.
+    async fn publish_all(&self, envelopes: Vec<Envelope>) -> Result<(), Self::Error> {
+        let deliveries = envelopes
+            .into_iter()
+            .map(|envelope| interfaces::MessageBroker::<M>::publish(self, envelope));
+        join_all(deliveries).await.into_iter().collect()
+    }
 }
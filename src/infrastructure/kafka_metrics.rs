@@ -0,0 +1,257 @@
+use crate::infrastructure::metrics::{Exposition, Histogram};
+use rdkafka::statistics::Statistics;
+use rdkafka::ClientContext;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Client context that hands librdkafka statistics over to `KafkaMetrics`.
+pub struct StatsContext {
+    metrics: Arc<KafkaMetrics>,
+}
+
+impl StatsContext {
+    pub fn new(metrics: Arc<KafkaMetrics>) -> StatsContext {
+        StatsContext { metrics }
+    }
+}
+
+impl ClientContext for StatsContext {
+    fn stats(&self, statistics: Statistics) {
+        *self.metrics.statistics.write().unwrap() = Some(statistics);
+    }
+}
+
+#[derive(Serialize)]
+pub struct BrokerView {
+    pub name: String,
+    pub state: String,
+    pub rtt_avg_us: Option<i64>,
+    pub rtt_p99_us: Option<i64>,
+    pub in_flight: i64,
+    pub queued: i64,
+    pub tx_errors: u64,
+    pub request_timeouts: u64,
+}
+
+#[derive(Serialize)]
+pub struct PartitionView {
+    pub topic: String,
+    pub partition: i32,
+    pub delivered: u64,
+    pub queued: Option<i64>,
+    pub in_flight: Option<i64>,
+}
+
+/// Failed deliveries have no partition, they are counted per topic.
+#[derive(Serialize)]
+pub struct FailureView {
+    pub topic: String,
+    pub failed: u64,
+}
+
+#[derive(Serialize)]
+pub struct LatencyView {
+    pub topic: String,
+    pub action: String,
+    pub histogram: Histogram,
+}
+
+#[derive(Serialize)]
+pub struct KafkaView {
+    pub collected_at: Option<i64>,
+    pub queued_messages: Option<u64>,
+    pub brokers: Vec<BrokerView>,
+    pub partitions: Vec<PartitionView>,
+    pub failures: Vec<FailureView>,
+    pub latency: Vec<LatencyView>,
+}
+
+/// Producer side view of the messaging system: the latest librdkafka
+/// statistics plus delivery outcomes recorded by `Kafka::publish`.
+#[derive(Default)]
+pub struct KafkaMetrics {
+    statistics: RwLock<Option<Statistics>>,
+    deliveries: Mutex<BTreeMap<(String, i32), u64>>,
+    failures: Mutex<BTreeMap<String, u64>>,
+    latency: Mutex<BTreeMap<(String, String), Histogram>>,
+}
+
+impl KafkaMetrics {
+    /// Records a delivery to `partition`, or a failed one when it is `None`.
+    pub fn record_delivery(
+        &self,
+        topic: &str,
+        action: &str,
+        partition: Option<i32>,
+        elapsed: Duration,
+    ) {
+        match partition {
+            Some(partition) => {
+                *self
+                    .deliveries
+                    .lock()
+                    .unwrap()
+                    .entry((topic.to_string(), partition))
+                    .or_default() += 1;
+            }
+            None => {
+                *self
+                    .failures
+                    .lock()
+                    .unwrap()
+                    .entry(topic.to_string())
+                    .or_default() += 1;
+            }
+        }
+        self.latency
+            .lock()
+            .unwrap()
+            .entry((topic.to_string(), action.to_string()))
+            .or_insert_with(Histogram::latency)
+            .observe(elapsed);
+    }
+
+    pub fn view(&self) -> KafkaView {
+        let statistics = self.statistics.read().unwrap();
+        let brokers = statistics
+            .iter()
+            .flat_map(|statistics| statistics.brokers.values())
+            .map(|broker| BrokerView {
+                name: broker.name.clone(),
+                state: broker.state.clone(),
+                rtt_avg_us: broker.rtt.as_ref().map(|rtt| rtt.avg),
+                rtt_p99_us: broker.rtt.as_ref().map(|rtt| rtt.p99),
+                in_flight: broker.waitresp_msg_cnt,
+                queued: broker.outbuf_msg_cnt,
+                tx_errors: broker.txerrs,
+                request_timeouts: broker.req_timeouts,
+            })
+            .collect();
+        let partitions = self
+            .deliveries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((topic, partition), delivered)| {
+                let stats = statistics
+                    .as_ref()
+                    .and_then(|statistics| statistics.topics.get(topic))
+                    .and_then(|topic| topic.partitions.get(partition));
+                PartitionView {
+                    topic: topic.clone(),
+                    partition: *partition,
+                    delivered: *delivered,
+                    queued: stats.map(|stats| stats.msgq_cnt + stats.xmit_msgq_cnt),
+                    in_flight: stats.map(|stats| stats.msgs_inflight),
+                }
+            })
+            .collect();
+        let failures = self
+            .failures
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(topic, failed)| FailureView {
+                topic: topic.clone(),
+                failed: *failed,
+            })
+            .collect();
+        let latency = self
+            .latency
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((topic, action), histogram)| LatencyView {
+                topic: topic.clone(),
+                action: action.clone(),
+                histogram: histogram.clone(),
+            })
+            .collect();
+        KafkaView {
+            collected_at: statistics.as_ref().map(|statistics| statistics.time),
+            queued_messages: statistics.as_ref().map(|statistics| statistics.msg_cnt),
+            brokers,
+            partitions,
+            failures,
+            latency,
+        }
+    }
+
+    pub fn write_metrics(&self, exposition: &mut Exposition) {
+        let view = self.view();
+        let broker = |name: &String| vec![("broker", name.clone())];
+        exposition.gauge(
+            "mesgmon_kafka_broker_rtt_seconds",
+            "Average round trip time to the broker.",
+            &view
+                .brokers
+                .iter()
+                .filter_map(|b| Some((broker(&b.name), b.rtt_avg_us? as f64 / 1e6)))
+                .collect::<Vec<_>>(),
+        );
+        exposition.gauge(
+            "mesgmon_kafka_broker_in_flight_messages",
+            "Messages awaiting a response from the broker.",
+            &view
+                .brokers
+                .iter()
+                .map(|b| (broker(&b.name), b.in_flight as f64))
+                .collect::<Vec<_>>(),
+        );
+        exposition.gauge(
+            "mesgmon_kafka_broker_queued_messages",
+            "Messages waiting in the broker output buffer.",
+            &view
+                .brokers
+                .iter()
+                .map(|b| (broker(&b.name), b.queued as f64))
+                .collect::<Vec<_>>(),
+        );
+        exposition.gauge(
+            "mesgmon_kafka_producer_queued_messages",
+            "Messages in the producer queue.",
+            &view
+                .queued_messages
+                .map(|queued| vec![(vec![], queued as f64)])
+                .unwrap_or_default(),
+        );
+        let partition = |p: &PartitionView| {
+            vec![
+                ("topic", p.topic.clone()),
+                ("partition", p.partition.to_string()),
+            ]
+        };
+        exposition.counter(
+            "mesgmon_kafka_delivered_total",
+            "Messages acknowledged by the broker.",
+            &view
+                .partitions
+                .iter()
+                .map(|p| (partition(p), p.delivered as f64))
+                .collect::<Vec<_>>(),
+        );
+        exposition.counter(
+            "mesgmon_kafka_delivery_errors_total",
+            "Messages that failed delivery.",
+            &view
+                .failures
+                .iter()
+                .map(|f| (vec![("topic", f.topic.clone())], f.failed as f64))
+                .collect::<Vec<_>>(),
+        );
+        exposition.histogram(
+            "mesgmon_kafka_delivery_latency_seconds",
+            "Time from send until the delivery report.",
+            &view
+                .latency
+                .iter()
+                .map(|l| {
+                    let labels = vec![("topic", l.topic.clone()), ("action", l.action.clone())];
+                    (labels, &l.histogram)
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+}
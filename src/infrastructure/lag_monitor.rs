@@ -0,0 +1,263 @@
+use crate::infrastructure::metrics::Exposition;
+use log::{error, info, warn};
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::{ClientConfig, Offset, TopicPartitionList};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone)]
+pub struct PartitionLag {
+    pub topic: String,
+    pub partition: i32,
+    /// `None` when the group has never committed on this partition.
+    pub committed: Option<i64>,
+    pub high: i64,
+    pub lag: i64,
+    pub stalled: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct GroupLag {
+    pub group: String,
+    pub total_lag: i64,
+    pub over_threshold: bool,
+    pub partitions: Vec<PartitionLag>,
+}
+
+struct Progress {
+    lag: i64,
+    since: Instant,
+    stalled: bool,
+}
+
+/// Periodically compares the committed offsets of downstream consumer groups
+/// with the high watermarks of the topics they consume.
+pub struct LagMonitor {
+    kafka_brokers: String,
+    groups: Vec<String>,
+    topics: Vec<String>,
+    threshold: i64,
+    stall_window: Duration,
+    progress: Mutex<HashMap<(String, String, i32), Progress>>,
+    latest: Mutex<Vec<GroupLag>>,
+}
+
+impl LagMonitor {
+    pub fn new(
+        kafka_brokers: &str,
+        groups: Vec<String>,
+        topics: Vec<String>,
+        threshold: i64,
+        stall_window: Duration,
+    ) -> LagMonitor {
+        LagMonitor {
+            kafka_brokers: kafka_brokers.to_string(),
+            groups,
+            topics,
+            threshold,
+            stall_window,
+            progress: Mutex::new(HashMap::new()),
+            latest: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let monitor = monitor.clone();
+                match tokio::task::spawn_blocking(move || monitor.check()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!("Failed to check consumer lag: {}", err),
+                    Err(err) => error!("Consumer lag check panicked: {}", err),
+                }
+            }
+        });
+    }
+
+    pub fn snapshot(&self) -> Vec<GroupLag> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub fn write_metrics(&self, exposition: &mut Exposition) {
+        let groups = self.snapshot();
+        let mut partitions = Vec::new();
+        let mut stalled = Vec::new();
+        for group in &groups {
+            for partition in &group.partitions {
+                let labels = vec![
+                    ("group", group.group.clone()),
+                    ("topic", partition.topic.clone()),
+                    ("partition", partition.partition.to_string()),
+                ];
+                partitions.push((labels.clone(), partition.lag as f64));
+                stalled.push((labels, if partition.stalled { 1.0 } else { 0.0 }));
+            }
+        }
+        exposition.gauge(
+            "mesgmon_consumer_lag",
+            "Messages between the committed offset and the high watermark.",
+            &partitions,
+        );
+        exposition.gauge(
+            "mesgmon_consumer_lag_stalled",
+            "Whether the lag has not decreased during the stall window.",
+            &stalled,
+        );
+        exposition.gauge(
+            "mesgmon_consumer_group_lag",
+            "Total lag of the consumer group.",
+            &groups
+                .iter()
+                .map(|group| (vec![("group", group.group.clone())], group.total_lag as f64))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        let mut groups = Vec::with_capacity(self.groups.len());
+        for group in &self.groups {
+            let consumer: BaseConsumer = ClientConfig::new()
+                .set("bootstrap.servers", &self.kafka_brokers)
+                .set("group.id", group)
+                .set("enable.auto.commit", "false")
+                .create()?;
+            let mut partitions = TopicPartitionList::new();
+            let mut watermarks = HashMap::new();
+            for topic in &self.topics {
+                let metadata = consumer.fetch_metadata(Some(topic), TIMEOUT)?;
+                for partition in metadata
+                    .topics()
+                    .iter()
+                    .flat_map(|topic| topic.partitions())
+                {
+                    partitions.add_partition(topic, partition.id());
+                    let (low, high) = consumer.fetch_watermarks(topic, partition.id(), TIMEOUT)?;
+                    watermarks.insert((topic.clone(), partition.id()), (low, high));
+                }
+            }
+            let committed = consumer.committed_offsets(partitions, TIMEOUT)?;
+            let mut lags = Vec::new();
+            for element in committed.elements() {
+                let key = (element.topic().to_string(), element.partition());
+                let (low, high) = watermarks[&key];
+                let committed = match element.offset() {
+                    Offset::Offset(offset) => Some(offset),
+                    _ => None,
+                };
+                let lag = (high - committed.unwrap_or(low)).max(0);
+                let stalled = self.track(group, &key.0, key.1, lag);
+                lags.push(PartitionLag {
+                    topic: key.0,
+                    partition: key.1,
+                    committed,
+                    high,
+                    lag,
+                    stalled,
+                });
+            }
+            let total_lag = lags.iter().map(|partition| partition.lag).sum();
+            let previous = self
+                .latest
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|previous| &previous.group == group)
+                .map(|previous| previous.over_threshold)
+                .unwrap_or(false);
+            let over_threshold = total_lag > self.threshold;
+            if over_threshold && !previous {
+                warn!(
+                    "Consumer group {} lag {} crossed the threshold of {}",
+                    group, total_lag, self.threshold
+                );
+            } else if !over_threshold && previous {
+                info!("Consumer group {} lag is back to {}", group, total_lag);
+            }
+            groups.push(GroupLag {
+                group: group.clone(),
+                total_lag,
+                over_threshold,
+                partitions: lags,
+            });
+        }
+        *self.latest.lock().unwrap() = groups;
+        Ok(())
+    }
+
+    /// Records the lag of a partition and returns whether it has stopped
+    /// decreasing for longer than the stall window.
+    fn track(&self, group: &str, topic: &str, partition: i32, lag: i64) -> bool {
+        let now = Instant::now();
+        let mut progress = self.progress.lock().unwrap();
+        let entry = progress
+            .entry((group.to_string(), topic.to_string(), partition))
+            .or_insert(Progress {
+                lag,
+                since: now,
+                stalled: false,
+            });
+        if lag == 0 || lag < entry.lag {
+            entry.since = now;
+        }
+        entry.lag = lag;
+        let stalled = lag > 0 && now.duration_since(entry.since) >= self.stall_window;
+        if stalled && !entry.stalled {
+            warn!(
+                "Consumer group {} has not made progress on {}/{} for {:?}, lag {}",
+                group, topic, partition, self.stall_window, lag
+            );
+        }
+        entry.stalled = stalled;
+        stalled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn monitor(stall_window: Duration) -> LagMonitor {
+        let groups = vec!["group".to_string()];
+        LagMonitor::new(
+            "localhost:9092",
+            groups,
+            vec!["topic".to_string()],
+            100,
+            stall_window,
+        )
+    }
+
+    #[test]
+    fn partitions_stall_when_their_lag_stops_decreasing() {
+        let monitor = monitor(Duration::from_millis(50));
+        assert!(!monitor.track("group", "topic", 0, 10));
+        thread::sleep(Duration::from_millis(60));
+        assert!(monitor.track("group", "topic", 0, 10));
+        assert!(!monitor.track("group", "topic", 1, 10));
+
+        assert!(!monitor.track("group", "topic", 0, 9));
+        thread::sleep(Duration::from_millis(60));
+        assert!(monitor.track("group", "topic", 0, 12));
+        assert!(!monitor.track("group", "topic", 0, 0));
+        assert!(!monitor.track("group", "topic", 0, 3));
+    }
+
+    #[test]
+    fn partitions_without_lag_are_not_stalled() {
+        let monitor = monitor(Duration::ZERO);
+        assert!(!monitor.track("group", "topic", 0, 0));
+        assert!(monitor.track("group", "topic", 0, 1));
+        assert!(!monitor.track("group", "topic", 0, 0));
+    }
+}
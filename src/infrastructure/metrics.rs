@@ -0,0 +1,168 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+#[derive(Serialize, Clone)]
+pub struct Histogram {
+    pub buckets: Vec<(f64, u64)>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn latency() -> Histogram {
+        Histogram {
+            buckets: LATENCY_BUCKETS.iter().map(|bound| (*bound, 0)).collect(),
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        for (bound, count) in self.buckets.iter_mut() {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Prometheus text exposition format writer.
+#[derive(Default)]
+pub struct Exposition {
+    body: String,
+}
+
+impl Exposition {
+    pub fn gauge(&mut self, name: &str, help: &str, samples: &[(Vec<(&str, String)>, f64)]) {
+        self.family(name, help, "gauge", samples);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, samples: &[(Vec<(&str, String)>, f64)]) {
+        self.family(name, help, "counter", samples);
+    }
+
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        samples: &[(Vec<(&str, String)>, &Histogram)],
+    ) {
+        self.header(name, help, "histogram");
+        for (labels, histogram) in samples {
+            for (bound, count) in &histogram.buckets {
+                let mut labels = labels.clone();
+                labels.push(("le", bound.to_string()));
+                self.sample(&format!("{name}_bucket"), &labels, *count as f64);
+            }
+            let mut labels = labels.clone();
+            labels.push(("le", "+Inf".to_string()));
+            self.sample(&format!("{name}_bucket"), &labels, histogram.count as f64);
+            labels.pop();
+            self.sample(&format!("{name}_sum"), &labels, histogram.sum);
+            self.sample(&format!("{name}_count"), &labels, histogram.count as f64);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.body
+    }
+
+    fn family(
+        &mut self,
+        name: &str,
+        help: &str,
+        kind: &str,
+        samples: &[(Vec<(&str, String)>, f64)],
+    ) {
+        self.header(name, help, kind);
+        for (labels, value) in samples {
+            self.sample(name, labels, *value);
+        }
+    }
+
+    fn header(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.body, "# HELP {name} {help}");
+        let _ = writeln!(self.body, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, String)], value: f64) {
+        self.body.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| {
+                    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+                    format!("{key}=\"{value}\"")
+                })
+                .collect();
+            let _ = write!(self.body, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.body, " {value}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::latency();
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_millis(40));
+        histogram.observe(Duration::from_secs(10));
+
+        let counts: Vec<u64> = histogram.buckets.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, [0, 0, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2]);
+        assert_eq!(histogram.count, 3);
+        assert!((histogram.sum - 10.043).abs() < 1e-9);
+    }
+
+    #[test]
+    fn exposition_writes_families_with_escaped_labels() {
+        let mut histogram = Histogram {
+            buckets: vec![(0.5, 0), (1.0, 0)],
+            count: 0,
+            sum: 0.0,
+        };
+        histogram.observe(Duration::from_millis(750));
+        let mut exposition = Exposition::default();
+        exposition.gauge("depth", "Queue depth.", &[(vec![], 3.0)]);
+        exposition.counter(
+            "sent_total",
+            "Sent events.",
+            &[(vec![("topic", "a\"b\\c".to_string())], 2.0)],
+        );
+        exposition.histogram(
+            "latency_seconds",
+            "Latency.",
+            &[(vec![("route", "/users".to_string())], &histogram)],
+        );
+
+        let expected = "\
+# HELP depth Queue depth.
+# TYPE depth gauge
+depth 3
+# HELP sent_total Sent events.
+# TYPE sent_total counter
+sent_total{topic=\"a\\\"b\\\\c\"} 2
+# HELP latency_seconds Latency.
+# TYPE latency_seconds histogram
+latency_seconds_bucket{route=\"/users\",le=\"0.5\"} 0
+latency_seconds_bucket{route=\"/users\",le=\"1\"} 1
+latency_seconds_bucket{route=\"/users\",le=\"+Inf\"} 1
+latency_seconds_sum{route=\"/users\"} 0.75
+latency_seconds_count{route=\"/users\"} 1
+";
+        assert_eq!(exposition.finish(), expected);
+    }
+}
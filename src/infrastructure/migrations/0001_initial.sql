@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS Users (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS Products (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    price BIGINT NOT NULL
+);
@@ -0,0 +1,23 @@
+CREATE OR REPLACE FUNCTION touch_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at = now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE Users ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE Products ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE TRIGGER users_touch_updated_at BEFORE UPDATE ON Users
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+CREATE TRIGGER products_touch_updated_at BEFORE UPDATE ON Products
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE INDEX IF NOT EXISTS users_updated_at ON Users (updated_at);
+CREATE INDEX IF NOT EXISTS products_updated_at ON Products (updated_at);
+
+CREATE TABLE Checkpoints (
+    name TEXT PRIMARY KEY,
+    position TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,7 @@
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_publication WHERE pubname = 'mesgmon_cdc') THEN
+        CREATE PUBLICATION mesgmon_cdc FOR TABLE Users, Products;
+    END IF;
+END;
+$$;
@@ -0,0 +1,30 @@
+CREATE TABLE Events (
+    position BIGSERIAL PRIMARY KEY,
+    transaction_id XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    stream_type TEXT NOT NULL,
+    stream_id TEXT NOT NULL,
+    sequence BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    data JSONB,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (stream_type, stream_id, sequence)
+);
+
+CREATE INDEX events_transaction_position ON Events (transaction_id, position);
+
+CREATE FUNCTION reject_event_changes() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'events are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER events_immutable BEFORE UPDATE OR DELETE ON Events
+    FOR EACH ROW EXECUTE FUNCTION reject_event_changes();
+
+CREATE TABLE Snapshots (
+    stream_type TEXT NOT NULL,
+    stream_id TEXT NOT NULL,
+    sequence BIGINT NOT NULL,
+    data JSONB,
+    PRIMARY KEY (stream_type, stream_id)
+);
@@ -0,0 +1,22 @@
+CREATE TABLE ProductCatalog (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    search_name TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    previous_price BIGINT,
+    revision BIGINT NOT NULL,
+    listed_at TIMESTAMPTZ NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX product_catalog_search_name ON ProductCatalog (search_name);
+
+CREATE TABLE UserEmailDomains (
+    user_id TEXT PRIMARY KEY,
+    domain TEXT NOT NULL
+);
+
+CREATE TABLE EmailDomainCounts (
+    domain TEXT PRIMARY KEY,
+    users BIGINT NOT NULL
+);
@@ -0,0 +1,57 @@
+CREATE TABLE UserHistory (
+    id TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    operation TEXT NOT NULL,
+    data JSONB,
+    principal TEXT,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to TIMESTAMPTZ,
+    PRIMARY KEY (id, version)
+);
+
+CREATE TABLE ProductHistory (LIKE UserHistory INCLUDING ALL);
+
+-- Closes the current version of the changed row and records the new one in
+-- the history table named by the first trigger argument. The acting
+-- principal is taken from the `mesgmon.principal` setting of the transaction.
+CREATE FUNCTION record_history() RETURNS trigger AS $$
+DECLARE
+    entity_id TEXT;
+    data JSONB;
+    previous BIGINT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        entity_id := OLD.id;
+    ELSE
+        entity_id := NEW.id;
+        data := to_jsonb(NEW) - 'updated_at';
+    END IF;
+    EXECUTE format(
+        'UPDATE %I SET valid_to = now() WHERE id = $1 AND valid_to IS NULL RETURNING version',
+        TG_ARGV[0]
+    ) INTO previous USING entity_id;
+    EXECUTE format(
+        'INSERT INTO %I (id, version, operation, data, principal, valid_from)
+        VALUES ($1, $2, $3, $4, $5, now())',
+        TG_ARGV[0]
+    ) USING
+        entity_id,
+        coalesce(previous, 0) + 1,
+        CASE TG_OP WHEN 'INSERT' THEN 'create' WHEN 'UPDATE' THEN 'update' ELSE 'delete' END,
+        data,
+        nullif(current_setting('mesgmon.principal', true), '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+INSERT INTO UserHistory (id, version, operation, data, principal, valid_from)
+SELECT id, 1, 'create', to_jsonb(Users) - 'updated_at', NULL, updated_at FROM Users;
+INSERT INTO ProductHistory (id, version, operation, data, principal, valid_from)
+SELECT id, 1, 'create', to_jsonb(Products) - 'updated_at', NULL, updated_at FROM Products;
+
+CREATE TRIGGER users_history AFTER INSERT OR UPDATE OR DELETE ON Users
+    FOR EACH ROW EXECUTE FUNCTION record_history('userhistory');
+CREATE TRIGGER products_history AFTER INSERT OR UPDATE OR DELETE ON Products
+    FOR EACH ROW EXECUTE FUNCTION record_history('producthistory');
+
+ALTER TABLE Events ADD COLUMN principal TEXT;
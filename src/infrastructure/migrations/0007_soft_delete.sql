@@ -0,0 +1,58 @@
+ALTER TABLE Users ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE Products ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE Users DROP CONSTRAINT users_email_key;
+CREATE UNIQUE INDEX users_email_key ON Users (email) WHERE deleted_at IS NULL;
+
+CREATE INDEX users_deleted_at ON Users (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX products_deleted_at ON Products (deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- Change data capture tells soft deletes and restores from updates by the
+-- old value of deleted_at.
+ALTER TABLE Users REPLICA IDENTITY FULL;
+ALTER TABLE Products REPLICA IDENTITY FULL;
+
+-- Setting deleted_at is recorded as a delete, clearing it as a restore and
+-- removing the row as a purge.
+CREATE OR REPLACE FUNCTION record_history() RETURNS trigger AS $$
+DECLARE
+    entity_id TEXT;
+    operation TEXT;
+    data JSONB;
+    previous BIGINT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        entity_id := OLD.id;
+        operation := 'purge';
+    ELSE
+        entity_id := NEW.id;
+        IF NEW.deleted_at IS NULL THEN
+            data := to_jsonb(NEW) - 'updated_at' - 'deleted_at';
+        END IF;
+        IF TG_OP = 'INSERT' THEN
+            operation := 'create';
+        ELSIF OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN
+            operation := 'delete';
+        ELSIF OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN
+            operation := 'restore';
+        ELSE
+            operation := 'update';
+        END IF;
+    END IF;
+    EXECUTE format(
+        'UPDATE %I SET valid_to = now() WHERE id = $1 AND valid_to IS NULL RETURNING version',
+        TG_ARGV[0]
+    ) INTO previous USING entity_id;
+    EXECUTE format(
+        'INSERT INTO %I (id, version, operation, data, principal, valid_from)
+        VALUES ($1, $2, $3, $4, $5, now())',
+        TG_ARGV[0]
+    ) USING
+        entity_id,
+        coalesce(previous, 0) + 1,
+        operation,
+        data,
+        nullif(current_setting('mesgmon.principal', true), '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,68 @@
+ALTER TABLE UserHistory ALTER COLUMN id TYPE UUID USING id::UUID;
+ALTER TABLE ProductHistory ALTER COLUMN id TYPE UUID USING id::UUID;
+ALTER TABLE Users ALTER COLUMN id TYPE UUID USING id::UUID;
+ALTER TABLE Products ALTER COLUMN id TYPE UUID USING id::UUID;
+ALTER TABLE Products ALTER COLUMN price TYPE NUMERIC(20, 0);
+ALTER TABLE Products ADD CONSTRAINT products_price_non_negative CHECK (price >= 0);
+
+ALTER TABLE Users ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE Products ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+-- Existing rows were created when their first version was recorded.
+ALTER TABLE Users DISABLE TRIGGER USER;
+ALTER TABLE Products DISABLE TRIGGER USER;
+UPDATE Users SET created_at = coalesce(
+    (SELECT min(valid_from) FROM UserHistory WHERE UserHistory.id = Users.id),
+    updated_at
+);
+UPDATE Products SET created_at = coalesce(
+    (SELECT min(valid_from) FROM ProductHistory WHERE ProductHistory.id = Products.id),
+    updated_at
+);
+ALTER TABLE Users ENABLE TRIGGER USER;
+ALTER TABLE Products ENABLE TRIGGER USER;
+
+-- Recorded events and versions are never rewritten, states without
+-- timestamps get them when they are read.
+CREATE OR REPLACE FUNCTION record_history() RETURNS trigger AS $$
+DECLARE
+    entity_id UUID;
+    operation TEXT;
+    data JSONB;
+    previous BIGINT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        entity_id := OLD.id;
+        operation := 'purge';
+    ELSE
+        entity_id := NEW.id;
+        IF NEW.deleted_at IS NULL THEN
+            data := to_jsonb(NEW) - 'deleted_at';
+        END IF;
+        IF TG_OP = 'INSERT' THEN
+            operation := 'create';
+        ELSIF OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN
+            operation := 'delete';
+        ELSIF OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN
+            operation := 'restore';
+        ELSE
+            operation := 'update';
+        END IF;
+    END IF;
+    EXECUTE format(
+        'UPDATE %I SET valid_to = now() WHERE id = $1 AND valid_to IS NULL RETURNING version',
+        TG_ARGV[0]
+    ) INTO previous USING entity_id;
+    EXECUTE format(
+        'INSERT INTO %I (id, version, operation, data, principal, valid_from)
+        VALUES ($1, $2, $3, $4, $5, now())',
+        TG_ARGV[0]
+    ) USING
+        entity_id,
+        coalesce(previous, 0) + 1,
+        operation,
+        data,
+        nullif(current_setting('mesgmon.principal', true), '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
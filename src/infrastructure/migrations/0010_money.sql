@@ -0,0 +1,15 @@
+CREATE TYPE money_amount AS (amount NUMERIC, currency CHAR(3));
+
+-- Prices were whole amounts without a currency, they are taken as USD.
+-- Recorded events and versions keep their prices and are converted the same
+-- way when they are read.
+ALTER TABLE Products DROP CONSTRAINT products_price_non_negative;
+ALTER TABLE Products ALTER COLUMN price TYPE money_amount USING ROW(price, 'USD')::money_amount;
+ALTER TABLE Products ADD CONSTRAINT products_price_non_negative CHECK ((price).amount >= 0);
+
+-- The catalog is rebuilt from the events.
+DELETE FROM ProductCatalog;
+DELETE FROM Checkpoints WHERE name = 'projection:product-catalog';
+ALTER TABLE ProductCatalog
+    ALTER COLUMN price TYPE money_amount USING NULL,
+    ALTER COLUMN previous_price TYPE money_amount USING NULL;
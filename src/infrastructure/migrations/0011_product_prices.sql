@@ -0,0 +1,29 @@
+CREATE TABLE ProductPrices (
+    id UUID PRIMARY KEY,
+    product_id UUID NOT NULL,
+    price money_amount NOT NULL CHECK ((price).amount >= 0),
+    effective_from TIMESTAMPTZ NOT NULL,
+    applied_at TIMESTAMPTZ
+);
+
+CREATE INDEX product_prices_product_id ON ProductPrices (product_id, effective_from);
+CREATE INDEX product_prices_scheduled ON ProductPrices (effective_from) WHERE applied_at IS NULL;
+
+-- The timeline starts with the price changes recorded in the history.
+INSERT INTO ProductPrices (id, product_id, price, effective_from, applied_at)
+SELECT
+    gen_random_uuid(),
+    id,
+    ROW((price->>'amount')::NUMERIC, price->>'currency')::money_amount,
+    valid_from,
+    valid_from
+FROM (
+    SELECT
+        id,
+        valid_from,
+        data->'price' AS price,
+        lag(data->'price') OVER (PARTITION BY id ORDER BY version) AS previous
+    FROM ProductHistory
+    WHERE data IS NOT NULL
+) versions
+WHERE previous IS DISTINCT FROM price;
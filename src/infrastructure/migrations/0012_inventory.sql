@@ -0,0 +1,20 @@
+CREATE TABLE Inventory (
+    product_id UUID PRIMARY KEY,
+    on_hand BIGINT NOT NULL,
+    reserved BIGINT NOT NULL DEFAULT 0,
+    available BIGINT GENERATED ALWAYS AS (on_hand - reserved) STORED,
+    low_stock_threshold BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (reserved >= 0 AND reserved <= on_hand)
+);
+
+CREATE TABLE Reservations (
+    id UUID PRIMARY KEY,
+    product_id UUID NOT NULL REFERENCES Inventory (product_id),
+    quantity BIGINT NOT NULL CHECK (quantity > 0),
+    status TEXT NOT NULL DEFAULT 'pending',
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX reservations_pending ON Reservations (expires_at) WHERE status = 'pending';
@@ -0,0 +1,43 @@
+CREATE TABLE Orders (
+    id UUID PRIMARY KEY,
+    -- Orders are financial records and outlive the users that placed them,
+    -- purging a user only removes the reference. None is open by then.
+    user_id UUID REFERENCES Users (id) ON DELETE SET NULL,
+    status TEXT NOT NULL,
+    total money_amount NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX orders_user_id ON Orders (user_id, created_at);
+
+CREATE TABLE OrderLines (
+    order_id UUID NOT NULL REFERENCES Orders (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    -- Lines keep their captured price when the product is purged.
+    product_id UUID REFERENCES Products (id) ON DELETE SET NULL,
+    quantity BIGINT NOT NULL CHECK (quantity > 0),
+    price money_amount NOT NULL,
+    PRIMARY KEY (order_id, position)
+);
+
+CREATE INDEX order_lines_product_id ON OrderLines (product_id);
+
+CREATE TRIGGER orders_touch_updated_at BEFORE UPDATE ON Orders
+    FOR EACH ROW EXECUTE FUNCTION touch_updated_at();
+
+CREATE FUNCTION reject_deleting_users_with_open_orders() RETURNS trigger AS $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM Orders
+        WHERE user_id = NEW.id AND status IN ('pending', 'paid', 'shipped')
+    ) THEN
+        RAISE EXCEPTION 'user has open orders' USING ERRCODE = 'restrict_violation';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_open_orders BEFORE UPDATE OF deleted_at ON Users
+    FOR EACH ROW WHEN (OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL)
+    EXECUTE FUNCTION reject_deleting_users_with_open_orders();
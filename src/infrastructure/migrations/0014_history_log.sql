@@ -0,0 +1,55 @@
+-- In crud storage mode the history tables are the change log the
+-- projections read, in commit order like Events.
+CREATE SEQUENCE history_position;
+
+ALTER TABLE UserHistory ADD COLUMN transaction_id XID8, ADD COLUMN position BIGINT;
+ALTER TABLE ProductHistory ADD COLUMN transaction_id XID8, ADD COLUMN position BIGINT;
+
+-- Versions recorded so far come first, in the order they were made.
+CREATE TEMPORARY TABLE numbered AS
+SELECT stream_type, id, version, row_number() OVER (ORDER BY valid_from, version) AS position
+FROM (
+    SELECT 'user' AS stream_type, id, version, valid_from FROM UserHistory
+    UNION ALL
+    SELECT 'product', id, version, valid_from FROM ProductHistory
+) versions;
+UPDATE UserHistory SET transaction_id = '0', position = numbered.position
+FROM numbered
+WHERE numbered.stream_type = 'user'
+AND numbered.id = UserHistory.id AND numbered.version = UserHistory.version;
+UPDATE ProductHistory SET transaction_id = '0', position = numbered.position
+FROM numbered
+WHERE numbered.stream_type = 'product'
+AND numbered.id = ProductHistory.id AND numbered.version = ProductHistory.version;
+SELECT setval('history_position', coalesce((SELECT max(position) FROM numbered), 0) + 1, false);
+DROP TABLE numbered;
+
+ALTER TABLE UserHistory
+    ALTER COLUMN transaction_id SET DEFAULT pg_current_xact_id(),
+    ALTER COLUMN transaction_id SET NOT NULL,
+    ALTER COLUMN position SET DEFAULT nextval('history_position'),
+    ALTER COLUMN position SET NOT NULL;
+ALTER TABLE ProductHistory
+    ALTER COLUMN transaction_id SET DEFAULT pg_current_xact_id(),
+    ALTER COLUMN transaction_id SET NOT NULL,
+    ALTER COLUMN position SET DEFAULT nextval('history_position'),
+    ALTER COLUMN position SET NOT NULL;
+CREATE INDEX user_history_log ON UserHistory (transaction_id, position);
+CREATE INDEX product_history_log ON ProductHistory (transaction_id, position);
+
+-- The versions as events, with the columns of Events.
+CREATE VIEW HistoryLog AS
+SELECT transaction_id, position, stream_type, id::TEXT AS stream_id,
+    CASE operation
+        WHEN 'create' THEN 'created'
+        WHEN 'update' THEN 'updated'
+        WHEN 'delete' THEN 'deleted'
+        WHEN 'restore' THEN 'restored'
+        ELSE 'purged'
+    END AS event_type,
+    data, valid_from AS recorded_at
+FROM (
+    SELECT 'user' AS stream_type, * FROM UserHistory
+    UNION ALL
+    SELECT 'product', * FROM ProductHistory
+) versions;
@@ -0,0 +1,5 @@
+-- A key is claimed in its own short transaction. Until the response is stored
+-- the row is pending, leased to the request that claimed it until
+-- `leased_until`, after which another request with the key may take it over.
+ALTER TABLE IdempotencyKeys ADD COLUMN lease UUID;
+ALTER TABLE IdempotencyKeys ADD COLUMN leased_until TIMESTAMPTZ;
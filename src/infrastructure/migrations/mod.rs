@@ -0,0 +1,19 @@
+/// Schema migrations in the order they are applied. Each one runs in its own
+/// transaction and is recorded in `schema_migrations`.
+pub const MIGRATIONS: &[(i32, &str)] = &[
+    (1, include_str!("0001_initial.sql")),
+    (2, include_str!("0002_replay.sql")),
+    (3, include_str!("0003_cdc.sql")),
+    (4, include_str!("0004_event_store.sql")),
+    (5, include_str!("0005_projections.sql")),
+    (6, include_str!("0006_history.sql")),
+    (7, include_str!("0007_soft_delete.sql")),
+    (8, include_str!("0008_native_types.sql")),
+    (9, include_str!("0009_idempotency.sql")),
+    (10, include_str!("0010_money.sql")),
+    (11, include_str!("0011_product_prices.sql")),
+    (12, include_str!("0012_inventory.sql")),
+    (13, include_str!("0013_orders.sql")),
+    (14, include_str!("0014_history_log.sql")),
+    (15, include_str!("0015_idempotency_leases.sql")),
+];
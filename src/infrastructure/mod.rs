@@ -1,7 +1,43 @@
+mod cdc;
+mod event_store;
+mod idempotency;
+mod inspector;
+mod inventory;
+mod kafka;
+mod kafka_metrics;
+mod lag_monitor;
+mod metrics;
+mod migrations;
+mod money;
+mod null_broker;
+mod orders;
 mod postgres;
+mod price_timeline;
+mod projections;
+mod publisher;
 mod repository;
-mod kafka;
+mod rows;
+mod table;
+mod unit_of_work;
+mod upgrade;
 
+pub use cdc::ChangeCapture;
+pub use event_store::{ConcurrencyConflict, EventRelay, EventStore};
+pub use idempotency::{Claim, IdempotencyStore, PendingResponse};
+pub use inspector::{MessageFilter, TopicInspector};
+pub use inventory::PostgresInventory;
+pub use kafka::Kafka;
+pub use kafka_metrics::KafkaMetrics;
+pub use lag_monitor::LagMonitor;
+pub use metrics::Exposition;
+pub use null_broker::NullBroker;
+pub use orders::PostgresOrderRepository;
 pub use postgres::Postgres;
+pub use price_timeline::PostgresPriceTimeline;
+pub use projections::{
+    CatalogQuery, EmailDomains, EventLog, ProductCatalog, Projector, ReadModel,
+};
+pub use publisher::{Backpressure, PublishRejected, QueuedBroker};
 pub use repository::Repository;
-pub use kafka::Kafka;
+pub use table::Table;
+pub use unit_of_work::{PostgresUnit, PostgresUnitOfWork};
@@ -0,0 +1,89 @@
+use crate::domain::models::Money;
+use bytes::{BufMut, BytesMut};
+use rust_decimal::Decimal;
+use std::error;
+use tokio_postgres::types::{to_sql_checked, FromSql, IsNull, Kind, ToSql, Type};
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Name of the composite type `Money` is stored as, `(amount, currency)`.
+const TYPE: &str = "money_amount";
+
+fn fields(ty: &Type) -> Option<(&Type, &Type)> {
+    match ty.kind() {
+        Kind::Composite(fields) if ty.name() == TYPE && fields.len() == 2 => {
+            Some((fields[0].type_(), fields[1].type_()))
+        }
+        _ => None,
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Error> {
+        let (amount, currency) = fields(ty).ok_or("money is stored as money_amount")?;
+        out.put_i32(2);
+        write_field(out, amount, &self.amount())?;
+        write_field(out, currency, &self.currency().code())?;
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        fields(ty).is_some()
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Money {
+    fn from_sql(ty: &Type, mut raw: &'a [u8]) -> Result<Money, Error> {
+        let (amount, currency) = fields(ty).ok_or("money is stored as money_amount")?;
+        if read_i32(&mut raw)? != 2 {
+            return Err("money_amount has two fields".into());
+        }
+        let amount: Decimal = read_field(&mut raw, amount)?;
+        let currency: &str = read_field(&mut raw, currency)?;
+        Ok(Money::new(amount, currency.parse()?)?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        fields(ty).is_some()
+    }
+}
+
+/// Writes a field of a composite value in the binary format: the type, the
+/// length and the value.
+fn write_field(out: &mut BytesMut, ty: &Type, value: &(dyn ToSql + Sync)) -> Result<(), Error> {
+    out.put_u32(ty.oid());
+    let start = out.len();
+    out.put_i32(0);
+    let length = match value.to_sql_checked(ty, out)? {
+        IsNull::Yes => -1,
+        IsNull::No => i32::try_from(out.len() - start - 4)?,
+    };
+    out[start..start + 4].copy_from_slice(&length.to_be_bytes());
+    Ok(())
+}
+
+fn read_field<'a, T: FromSql<'a>>(raw: &mut &'a [u8], ty: &Type) -> Result<T, Error> {
+    read_i32(raw)?;
+    let length = read_i32(raw)?;
+    if length < 0 {
+        return T::from_sql_null(ty);
+    }
+    let length = length as usize;
+    if raw.len() < length {
+        return Err("money_amount is truncated".into());
+    }
+    let (value, rest) = raw.split_at(length);
+    *raw = rest;
+    T::from_sql(ty, value)
+}
+
+fn read_i32(raw: &mut &[u8]) -> Result<i32, Error> {
+    if raw.len() < 4 {
+        return Err("money_amount is truncated".into());
+    }
+    let (value, rest) = raw.split_at(4);
+    *raw = rest;
+    Ok(i32::from_be_bytes(value.try_into()?))
+}
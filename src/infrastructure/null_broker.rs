@@ -0,0 +1,22 @@
+use crate::domain::interfaces;
+use crate::domain::interfaces::Identifiable;
+use crate::domain::models::Envelope;
+use axum::async_trait;
+use std::error::Error;
+
+/// Broker for services whose events are published from another source, such
+/// as change data capture, so that writes are not announced twice.
+pub struct NullBroker;
+
+#[async_trait]
+impl<M: Identifiable + Sync + ?Sized> interfaces::MessageBroker<M> for NullBroker {
+    type Error = Box<dyn Error + Send + Sync>;
+
+    async fn send(&self, _topic: &str, _action: &str, _message: &M) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn publish(&self, _envelope: Envelope) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::domain::interfaces;
+use crate::domain::models::{Order, OrderLine, OrderStatus};
+use axum::async_trait;
+use deadpool_postgres::Pool;
+use std::collections::HashMap;
+use std::error;
+use std::io;
+use std::io::ErrorKind;
+use tokio_postgres::{GenericClient, Row};
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Orders kept in the `Orders` table, their lines in `OrderLines`.
+pub struct PostgresOrderRepository {
+    pool: Pool,
+}
+
+impl PostgresOrderRepository {
+    pub fn new(pool: Pool) -> PostgresOrderRepository {
+        PostgresOrderRepository { pool }
+    }
+
+    /// Orders of the rows with their lines, in the order of the rows.
+    async fn load(client: &impl GenericClient, rows: &[Row]) -> Result<Vec<Order>, Error> {
+        let ids = rows
+            .iter()
+            .map(|row| row.try_get("id"))
+            .collect::<Result<Vec<Uuid>, _>>()?;
+        let mut lines: HashMap<Uuid, Vec<OrderLine>> = HashMap::new();
+        for row in client
+            .query(
+                "SELECT order_id, product_id, quantity, price FROM OrderLines
+                WHERE order_id = ANY($1) ORDER BY order_id, position",
+                &[&ids],
+            )
+            .await?
+        {
+            lines
+                .entry(row.try_get("order_id")?)
+                .or_default()
+                .push(OrderLine {
+                    product_id: row.try_get("product_id")?,
+                    quantity: row.try_get("quantity")?,
+                    price: row.try_get("price")?,
+                });
+        }
+        rows.iter()
+            .map(|row| {
+                let id = row.try_get("id")?;
+                Ok(Order {
+                    id,
+                    user_id: row.try_get("user_id")?,
+                    status: row.try_get::<_, &str>("status")?.parse()?,
+                    lines: lines.remove(&id).unwrap_or_default(),
+                    total: row.try_get("total")?,
+                    created_at: row.try_get("created_at")?,
+                    updated_at: row.try_get("updated_at")?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl interfaces::OrderRepository for PostgresOrderRepository {
+    type Error = Error;
+
+    async fn add(&self, order: Order) -> Result<Order, Error> {
+        let mut connection = self.pool.get().await?;
+        let transaction = connection.transaction().await?;
+        // Holds off deleting the user until the order is stored.
+        let user = transaction
+            .query_opt(
+                "SELECT id FROM Users WHERE id = $1 AND deleted_at IS NULL FOR SHARE",
+                &[&order.user_id],
+            )
+            .await?;
+        if user.is_none() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "user not found").into());
+        }
+        let row = transaction
+            .query_one(
+                "INSERT INTO Orders (id, user_id, status, total) VALUES ($1, $2, $3, $4)
+                RETURNING *",
+                &[
+                    &order.id,
+                    &order.user_id,
+                    &order.status.as_str(),
+                    &order.total,
+                ],
+            )
+            .await?;
+        for (position, line) in order.lines.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT INTO OrderLines (order_id, position, product_id, quantity, price)
+                    VALUES ($1, $2, $3, $4, $5)",
+                    &[
+                        &order.id,
+                        &(position as i32),
+                        &line.product_id,
+                        &line.quantity,
+                        &line.price,
+                    ],
+                )
+                .await?;
+        }
+        let order = Self::load(&*transaction, &[row]).await?.remove(0);
+        transaction.commit().await?;
+        Ok(order)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Order>, Error> {
+        let connection = self.pool.get().await?;
+        let rows = connection
+            .query("SELECT * FROM Orders WHERE id = $1", &[&id])
+            .await?;
+        Ok(Self::load(&**connection, &rows).await?.pop())
+    }
+
+    async fn for_user(&self, user_id: Uuid) -> Result<Vec<Order>, Error> {
+        let connection = self.pool.get().await?;
+        let rows = connection
+            .query(
+                "SELECT * FROM Orders WHERE user_id = $1 ORDER BY created_at DESC",
+                &[&user_id],
+            )
+            .await?;
+        Self::load(&**connection, &rows).await
+    }
+
+    async fn set_status(
+        &self,
+        id: Uuid,
+        from: OrderStatus,
+        to: OrderStatus,
+    ) -> Result<Option<Order>, Error> {
+        let connection = self.pool.get().await?;
+        let rows = connection
+            .query(
+                "UPDATE Orders SET status = $3 WHERE id = $1 AND status = $2 RETURNING *",
+                &[&id, &from.as_str(), &to.as_str()],
+            )
+            .await?;
+        Ok(Self::load(&**connection, &rows).await?.pop())
+    }
+}
@@ -1,16 +1,54 @@
+use crate::domain::dto::Scan;
 use crate::domain::interfaces;
-use crate::domain::models::{Product, User};
+use crate::domain::models::{Change, Version};
+use crate::domain::principal;
+use crate::infrastructure::migrations::MIGRATIONS;
+use crate::infrastructure::table::{Statements, Table};
+use crate::infrastructure::upgrade::upgrade;
 use axum::async_trait;
+use chrono::{DateTime, Utc};
 use deadpool_postgres::{Manager, Pool, Transaction};
+use log::info;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::error;
 use std::str::FromStr;
-use tokio_postgres::{Config, NoTls};
+use std::sync::{Arc, RwLock};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Config, GenericClient, NoTls, Row};
 use uuid::Uuid;
 
 type Error = Box<dyn error::Error + Send + Sync>;
 
+/// Bind parameters Postgres accepts in one statement.
+const MAX_PARAMS: usize = u16::MAX as usize;
+
 pub struct Postgres {
     pool: Pool,
+    statements: RwLock<HashMap<&'static str, Arc<Statements>>>,
+}
+
+/// `Scan` converted to the parameter types of the scan queries.
+struct ScanParams {
+    after: Option<Uuid>,
+    ids: Option<Vec<Uuid>>,
+    updated_since: Option<DateTime<Utc>>,
+    limit: i64,
+}
+
+impl ScanParams {
+    fn new(scan: &Scan) -> ScanParams {
+        ScanParams {
+            after: scan.after,
+            ids: scan.ids.clone(),
+            updated_since: scan.updated_since,
+            limit: scan.limit as i64,
+        }
+    }
+
+    fn as_params(&self) -> [&(dyn ToSql + Sync); 4] {
+        [&self.after, &self.ids, &self.updated_since, &self.limit]
+    }
 }
 
 impl Postgres {
@@ -18,7 +56,64 @@ impl Postgres {
         let config = Config::from_str(database_uri)?;
         let manager = Manager::new(config, NoTls);
         let pool = Pool::builder(manager).build()?;
-        Ok(Postgres { pool })
+        Ok(Postgres {
+            pool,
+            statements: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn pool(&self) -> Pool {
+        self.pool.clone()
+    }
+
+    pub async fn migrate(&self) -> Result<(), Error> {
+        let mut connection = self.pool.get().await?;
+        connection
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS schema_migrations (
+                    version INTEGER PRIMARY KEY,
+                    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+                )",
+            )
+            .await?;
+        for (version, script) in MIGRATIONS {
+            let transaction = connection.transaction().await?;
+            transaction
+                .execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE", &[])
+                .await?;
+            let applied = transaction
+                .query_opt(
+                    "SELECT version FROM schema_migrations WHERE version = $1",
+                    &[version],
+                )
+                .await?;
+            if applied.is_none() {
+                info!("Applying migration {}", version);
+                transaction.batch_execute(script).await?;
+                transaction
+                    .execute(
+                        "INSERT INTO schema_migrations (version) VALUES ($1)",
+                        &[version],
+                    )
+                    .await?;
+            }
+            transaction.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// Attributes the changes made in the transaction to the principal of the
+    /// current request, for the history triggers.
+    pub(super) async fn attribute<C: GenericClient>(transaction: &C) -> Result<(), Error> {
+        if let Some(principal) = principal::current() {
+            transaction
+                .execute(
+                    "SELECT set_config('mesgmon.principal', $1, true)",
+                    &[&principal],
+                )
+                .await?;
+        }
+        Ok(())
     }
 
     pub async fn commit_or_rollback<T>(
@@ -34,134 +129,413 @@ impl Postgres {
     }
 }
 
+/// State recorded by a row of a history table, `None` for a delete.
+fn version_state<T: Table>(row: &Row) -> Result<Option<T>, Error> {
+    let data: Option<Value> = row.get("data");
+    let valid_from = row.get("valid_from");
+    let created_at = row.get::<_, Option<_>>("created_at").unwrap_or(valid_from);
+    Ok(data
+        .map(|data| serde_json::from_value(upgrade(data, created_at, valid_from)))
+        .transpose()?)
+}
+
+/// Runs the statements of the generic `Database` implementation, on pooled
+/// connections or in the transaction of a unit of work.
 #[async_trait]
-impl interfaces::Database<User> for Postgres {
-    type Error = Error;
+pub trait Executor: Send + Sync {
+    fn statements<T: Table>(&self) -> Arc<Statements>;
 
-    async fn add(&self, item: User) -> Result<(), Self::Error> {
-        let mut connection = self.pool.get().await?;
-        let statement = connection
-            .prepare_cached("INSERT INTO Users (id, name, email) VALUES ($1, $2, $3)")
-            .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction
-            .execute(&statement, &[&item.id.to_string(), &item.name, &item.email])
-            .await;
-        Self::commit_or_rollback(transaction, &result).await
+    async fn read(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error>;
+
+    /// Runs a statement attributed to the current principal.
+    async fn write(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error>;
+
+    /// Sets the savepoint `name` when the statements run in a transaction.
+    async fn savepoint(&self, _name: &str) -> Result<(), Error> {
+        Ok(())
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), Self::Error> {
-        let mut connection = self.pool.get().await?;
-        let statement = connection
-            .prepare_cached("DELETE FROM Users WHERE id = $1")
-            .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction.execute(&statement, &[&id.to_string()]).await;
-        Self::commit_or_rollback(transaction, &result).await
+    /// Releases the savepoint `name`, rolling back to it first when `undo`.
+    async fn release(&self, _name: &str, _undo: bool) -> Result<(), Error> {
+        Ok(())
     }
+}
 
-    async fn update(&self, item: User) -> Result<(), Self::Error> {
-        let mut connection = self.pool.get().await?;
-        let statement = connection
-            .prepare_cached("UPDATE Users SET name = $1, email = $2 WHERE id = $3")
-            .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction
-            .execute(&statement, &[&item.name, &item.email, &item.id.to_string()])
-            .await;
-        Self::commit_or_rollback(transaction, &result).await
+#[async_trait]
+impl Executor for Postgres {
+    fn statements<T: Table>(&self) -> Arc<Statements> {
+        if let Some(statements) = self.statements.read().unwrap().get(T::NAME) {
+            return statements.clone();
+        }
+        self.statements
+            .write()
+            .unwrap()
+            .entry(T::NAME)
+            .or_insert_with(|| Arc::new(Statements::new::<T>()))
+            .clone()
+    }
+
+    async fn read(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        let connection = self.pool.get().await?;
+        let statement = connection.prepare_cached(statement).await?;
+        Ok(connection.query(&statement, params).await?)
     }
 
-    async fn get(&self, id: Uuid) -> Result<Option<User>, Self::Error> {
+    /// Runs a statement in its own transaction.
+    async fn write(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
         let mut connection = self.pool.get().await?;
-        let statement = connection
-            .prepare_cached("SELECT (name, email) FROM Users WHERE id = $1")
-            .await?;
+        let statement = connection.prepare_cached(statement).await?;
         let transaction = connection.transaction().await?;
-        let result = transaction.query(&statement, &[&id.to_string()]).await;
-        if result.is_err() {
-            Self::commit_or_rollback(transaction, &result).await?;
-            return Err(result.unwrap_err().into());
-        }
-        transaction.commit().await?;
-        let row = &result.unwrap()[0];
-        match (row.get::<_, String>("name"), row.get::<_, String>("email")) {
-            (name, email) if !name.is_empty() && !email.is_empty() => {
-                let user = User { id, name, email };
-                Ok(Some(user))
+        Postgres::attribute(&*transaction).await?;
+        let result = transaction.query(&statement, params).await;
+        Postgres::commit_or_rollback(transaction, &result).await?;
+        Ok(result?)
+    }
+}
+
+#[async_trait]
+impl<T: Table, E: Executor> interfaces::Database<T> for E {
+    type Error = Error;
+
+    async fn add(&self, item: T) -> Result<T, Self::Error> {
+        let statements = self.statements::<T>();
+        let params = item.to_params();
+        let rows = self.write(&statements.insert, &as_params(&params)).await?;
+        T::from_row(rows.first().ok_or("insert returned no row")?)
+    }
+
+    async fn add_all(&self, items: Vec<T>) -> Result<Vec<T>, Self::Error> {
+        self.savepoint("add_all").await?;
+        let result = async {
+            let mut added = Vec::with_capacity(items.len());
+            for chunk in items.chunks(MAX_PARAMS / T::COLUMNS.len()) {
+                let statement = Statements::insert_all::<T>(chunk.len());
+                let params: Vec<_> = chunk.iter().flat_map(Table::to_params).collect();
+                let rows = self.write(&statement, &as_params(&params)).await?;
+                for row in &rows {
+                    added.push(T::from_row(row)?);
+                }
             }
-            _ => Ok(None),
+            Ok(added)
         }
+        .await;
+        self.release("add_all", result.is_err()).await?;
+        result
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, Self::Error> {
+        let statements = self.statements::<T>();
+        let rows = self.write(&statements.delete, &[&id]).await?;
+        Ok(!rows.is_empty())
+    }
+
+    async fn update(&self, item: T) -> Result<Option<T>, Self::Error> {
+        let statements = self.statements::<T>();
+        let params = item.to_params();
+        let rows = self.write(&statements.update, &as_params(&params)).await?;
+        rows.first().map(T::from_row).transpose()
+    }
+
+    async fn update_fields(&self, item: T, fields: &[String]) -> Result<Option<T>, Self::Error> {
+        let Some((statement, indexes)) = Statements::update_fields::<T>(fields) else {
+            return self.update(item).await;
+        };
+        let params = item.to_params();
+        let params: Vec<_> = indexes
+            .iter()
+            .map(|&index| params[index].as_ref() as &(dyn ToSql + Sync))
+            .collect();
+        let rows = self.write(&statement, &params).await?;
+        rows.first().map(T::from_row).transpose()
+    }
+
+    async fn upsert(&self, item: T) -> Result<Option<Change<T>>, Self::Error> {
+        let statements = self.statements::<T>();
+        let params = item.to_params();
+        let rows = self.write(&statements.upsert, &as_params(&params)).await?;
+        let Some(row) = rows.first() else {
+            return Ok(None);
+        };
+        let item = T::from_row(row)?;
+        if row.try_get("inserted")? {
+            Ok(Some(Change::Created(item)))
+        } else {
+            Ok(Some(Change::Updated(item)))
+        }
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<T>, Self::Error> {
+        let statements = self.statements::<T>();
+        let rows = self.read(&statements.get, &[&id]).await?;
+        rows.first().map(T::from_row).transpose()
+    }
+
+    async fn scan(&self, scan: &Scan) -> Result<Vec<T>, Self::Error> {
+        let statements = self.statements::<T>();
+        let params = ScanParams::new(scan);
+        let rows = self.read(&statements.scan, &params.as_params()).await?;
+        rows.iter().map(T::from_row).collect()
+    }
+
+    async fn get_as_of(&self, id: Uuid, as_of: DateTime<Utc>) -> Result<Option<T>, Self::Error> {
+        let statements = self.statements::<T>();
+        let rows = self.read(&statements.get_version, &[&id, &as_of]).await?;
+        let Some(row) = rows.first() else {
+            return Ok(None);
+        };
+        Ok(version_state(row)?)
+    }
+
+    async fn history(&self, id: Uuid) -> Result<Vec<Version<T>>, Self::Error> {
+        let statements = self.statements::<T>();
+        let rows = self.read(&statements.history, &[&id]).await?;
+        rows.iter()
+            .map(|row| {
+                Ok(Version {
+                    version: row.get("version"),
+                    operation: row.get("operation"),
+                    principal: row.get("principal"),
+                    valid_from: row.get("valid_from"),
+                    valid_to: row.get("valid_to"),
+                    state: version_state(row)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<bool, Self::Error> {
+        let statements = self.statements::<T>();
+        let rows = self.write(&statements.restore, &[&id]).await?;
+        Ok(!rows.is_empty())
+    }
+
+    async fn purge(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Uuid>, Self::Error> {
+        let statements = self.statements::<T>();
+        let rows = self
+            .write(&statements.purge, &[&deleted_before, &(limit as i64)])
+            .await?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
     }
 }
 
+fn as_params(params: &[Box<dyn ToSql + Send + Sync>]) -> Vec<&(dyn ToSql + Sync)> {
+    params
+        .iter()
+        .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+        .collect()
+}
+
 #[async_trait]
-impl interfaces::Database<Product> for Postgres {
+impl interfaces::CheckpointStore for Postgres {
     type Error = Error;
 
-    async fn add(&self, item: Product) -> Result<(), Self::Error> {
-        let mut connection = self.pool.get().await?;
+    async fn load(&self, name: &str) -> Result<Option<String>, Self::Error> {
+        let connection = self.pool.get().await?;
         let statement = connection
-            .prepare_cached("INSERT INTO Products (id, name, price) VALUES ($1, $2, $3)")
+            .prepare_cached("SELECT position FROM Checkpoints WHERE name = $1")
             .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction
-            .execute(
-                &statement,
-                &[&item.id.to_string(), &item.name, &(item.price as i64)],
-            )
-            .await;
-        Self::commit_or_rollback(transaction, &result).await
+        let row = connection.query_opt(&statement, &[&name]).await?;
+        Ok(row.map(|row| row.get("position")))
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), Self::Error> {
-        let mut connection = self.pool.get().await?;
+    async fn save(&self, name: &str, position: &str) -> Result<(), Self::Error> {
+        let connection = self.pool.get().await?;
         let statement = connection
-            .prepare_cached("DELETE FROM Products WHERE id = $1")
+            .prepare_cached(
+                "INSERT INTO Checkpoints (name, position) VALUES ($1, $2)
+                ON CONFLICT (name) DO UPDATE SET position = $2, updated_at = now()",
+            )
             .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction.execute(&statement, &[&id.to_string()]).await;
-        Self::commit_or_rollback(transaction, &result).await
+        connection.execute(&statement, &[&name, &position]).await?;
+        Ok(())
     }
 
-    async fn update(&self, item: Product) -> Result<(), Self::Error> {
-        let mut connection = self.pool.get().await?;
+    async fn clear(&self, name: &str) -> Result<(), Self::Error> {
+        let connection = self.pool.get().await?;
         let statement = connection
-            .prepare_cached("UPDATE Products SET name = $1, price = $2 WHERE id = $3")
+            .prepare_cached("DELETE FROM Checkpoints WHERE name = $1")
             .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction
-            .execute(
-                &statement,
-                &[&item.name, &(item.price as i64), &item.id.to_string()],
-            )
-            .await;
-        Self::commit_or_rollback(transaction, &result).await
+        connection.execute(&statement, &[&name]).await?;
+        Ok(())
     }
+}
 
-    async fn get(&self, id: Uuid) -> Result<Option<Product>, Self::Error> {
-        let mut connection = self.pool.get().await?;
-        let statement = connection
-            .prepare_cached("SELECT (name, price) FROM Products WHERE id = $1")
-            .await?;
-        let transaction = connection.transaction().await?;
-        let result = transaction.query(&statement, &[&id.to_string()]).await;
-        if result.is_err() {
-            Self::commit_or_rollback(transaction, &result).await?;
-            return Err(result.unwrap_err().into());
-        }
-        transaction.commit().await?;
-        let row = &result.unwrap()[0];
-        match (row.get::<_, String>("name"), row.get::<_, i64>("price")) {
-            (name, price) if !name.is_empty() => {
-                let product = Product {
-                    id,
-                    name,
-                    price: price as usize,
-                };
-                Ok(Some(product))
-            }
-            _ => Ok(None),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::interfaces::{
+        Database, Identifiable, Repositories, UnitOfWork, UnitOfWorkFactory,
+    };
+    use crate::domain::models::{Currency, Money, Product, User};
+    use crate::infrastructure::PostgresUnitOfWork;
+    use crate::test_support::{postgres, product, user, Recorder};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// Checks that deletes and updates of `T` tell when there is no such item.
+    async fn report_missing<T: Table + Clone>(postgres: &Postgres, item: fn() -> T) {
+        let missing = item();
+        let id = missing.id().parse().unwrap();
+        assert!(!Database::<T>::delete(postgres, id).await.unwrap());
+        assert!(postgres.update(missing).await.unwrap().is_none());
+
+        let added = postgres.add(item()).await.unwrap();
+        let id = added.id().parse().unwrap();
+        let updated = postgres.update(added.clone()).await.unwrap().unwrap();
+        let (created_at, updated_at) = added.timestamps().unwrap();
+        assert_eq!(updated.timestamps().unwrap().0, created_at);
+        assert!(updated.timestamps().unwrap().1 >= updated_at);
+        assert!(Database::<T>::delete(postgres, id).await.unwrap());
+        assert!(!Database::<T>::delete(postgres, id).await.unwrap());
+        assert!(postgres.update(added).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn delete_and_update_report_missing_items() {
+        let postgres = postgres().await;
+        report_missing(&postgres, user).await;
+        report_missing(&postgres, product).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn product_prices_keep_their_currency_and_scale() {
+        let postgres = postgres().await;
+        let price: Money =
+            serde_json::from_value(json!({"amount": "19.995", "currency": "EUR"})).unwrap();
+        assert_eq!(price.amount().to_string(), "20.00");
+        let invalid = [
+            json!({"amount": "-1", "currency": "EUR"}),
+            json!({"amount": "1", "currency": "JPY"}),
+        ];
+        for price in invalid {
+            assert!(serde_json::from_value::<Money>(price).is_err());
         }
+
+        let added = postgres.add(Product { price, ..product() }).await.unwrap();
+        assert_eq!(added.price, price);
+        let stored = Database::<Product>::get(&*postgres, added.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.price.amount().to_string(), "20.00");
+        assert_eq!(stored.price.currency(), Currency::Eur);
+        let recorded = Database::<Product>::get_as_of(&*postgres, added.id, Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(recorded.price, price);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn unit_of_work_commits_and_publishes_together() {
+        let postgres = postgres().await;
+        let recorder = Arc::new(Recorder::default());
+        let units = PostgresUnitOfWork::new(postgres.clone(), recorder.clone());
+
+        let unit = units.begin().await.unwrap();
+        let users = Repositories::<User>::repository(&unit);
+        let products = Repositories::<Product>::repository(&unit);
+        let user = users.add(user()).await.unwrap();
+        let product = products.add(product()).await.unwrap();
+        unit.broker()
+            .send("user-events", "create", &user)
+            .await
+            .unwrap();
+        unit.broker()
+            .send("product-events", "create", &product)
+            .await
+            .unwrap();
+        assert!(users.get(user.id).await.unwrap().is_some());
+        assert!(Database::<User>::get(&*postgres, user.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(recorder.sent.lock().unwrap().is_empty());
+
+        unit.commit().await.unwrap();
+        assert!(Database::<User>::get(&*postgres, user.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(Database::<Product>::get(&*postgres, product.id)
+            .await
+            .unwrap()
+            .is_some());
+        let expected = vec![
+            ("user-events".to_string(), "create".to_string(), user.id()),
+            (
+                "product-events".to_string(),
+                "create".to_string(),
+                product.id(),
+            ),
+        ];
+        assert_eq!(*recorder.sent.lock().unwrap(), expected);
+        assert!(unit.commit().await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn unit_of_work_rolls_back_changes_and_events() {
+        let postgres = postgres().await;
+        let recorder = Arc::new(Recorder::default());
+        let units = PostgresUnitOfWork::new(postgres.clone(), recorder.clone());
+
+        let unit = units.begin().await.unwrap();
+        let user = Repositories::<User>::repository(&unit)
+            .add(user())
+            .await
+            .unwrap();
+        let product = Repositories::<Product>::repository(&unit)
+            .add(product())
+            .await
+            .unwrap();
+        unit.broker()
+            .send("user-events", "create", &user)
+            .await
+            .unwrap();
+        unit.rollback().await.unwrap();
+        assert!(Database::<User>::get(&*postgres, user.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(Database::<Product>::get(&*postgres, product.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let unit = units.begin().await.unwrap();
+        let dropped = Repositories::<User>::repository(&unit)
+            .add(self::user())
+            .await
+            .unwrap();
+        drop(unit);
+        assert!(Database::<User>::get(&*postgres, dropped.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(recorder.sent.lock().unwrap().is_empty());
     }
 }
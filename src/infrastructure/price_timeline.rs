@@ -0,0 +1,146 @@
+use crate::domain::interfaces;
+use crate::domain::models::{Money, PriceChange};
+use crate::infrastructure::postgres::Executor;
+use crate::infrastructure::PostgresUnit;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use std::error;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Price timeline kept in the `ProductPrices` table.
+pub struct PostgresPriceTimeline {
+    connection: Connection,
+}
+
+enum Connection {
+    Pool(Pool),
+    /// The transaction of a unit of work.
+    Unit(PostgresUnit),
+}
+
+impl PostgresPriceTimeline {
+    pub fn new(pool: Pool) -> PostgresPriceTimeline {
+        PostgresPriceTimeline {
+            connection: Connection::Pool(pool),
+        }
+    }
+
+    /// Timeline whose changes belong to `unit`.
+    pub(crate) fn in_unit(unit: PostgresUnit) -> PostgresPriceTimeline {
+        PostgresPriceTimeline {
+            connection: Connection::Unit(unit),
+        }
+    }
+
+    async fn query(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        match &self.connection {
+            Connection::Pool(pool) => {
+                let connection = pool.get().await?;
+                let statement = connection.prepare_cached(statement).await?;
+                Ok(connection.query(&statement, params).await?)
+            }
+            Connection::Unit(unit) => unit.read(statement, params).await,
+        }
+    }
+}
+
+fn price_change(row: &Row) -> Result<PriceChange, Error> {
+    Ok(PriceChange {
+        id: row.try_get("id")?,
+        product_id: row.try_get("product_id")?,
+        price: row.try_get("price")?,
+        effective_from: row.try_get("effective_from")?,
+        applied_at: row.try_get("applied_at")?,
+    })
+}
+
+#[async_trait]
+impl interfaces::PriceTimeline for PostgresPriceTimeline {
+    type Error = Error;
+
+    async fn record(
+        &self,
+        product_id: Uuid,
+        price: &Money,
+        at: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        self.query(
+            "INSERT INTO ProductPrices (id, product_id, price, effective_from, applied_at)
+            SELECT $1, $2, $3::money_amount, $4, $4
+            WHERE (
+                SELECT price FROM ProductPrices
+                WHERE product_id = $2 AND applied_at IS NOT NULL
+                ORDER BY applied_at DESC LIMIT 1
+            ) IS DISTINCT FROM $3::money_amount",
+            &[&Uuid::new_v4(), &product_id, price, &at],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn schedule(
+        &self,
+        product_id: Uuid,
+        price: &Money,
+        effective_from: DateTime<Utc>,
+    ) -> Result<PriceChange, Error> {
+        let rows = self
+            .query(
+                "INSERT INTO ProductPrices (id, product_id, price, effective_from)
+                VALUES ($1, $2, $3, $4) RETURNING *",
+                &[&Uuid::new_v4(), &product_id, price, &effective_from],
+            )
+            .await?;
+        price_change(rows.first().ok_or("the scheduled price was not returned")?)
+    }
+
+    async fn history(&self, product_id: Uuid) -> Result<Vec<PriceChange>, Error> {
+        let rows = self
+            .query(
+                "SELECT * FROM ProductPrices WHERE product_id = $1
+                ORDER BY effective_from, applied_at NULLS LAST",
+                &[&product_id],
+            )
+            .await?;
+        rows.iter().map(price_change).collect()
+    }
+
+    async fn due(&self, now: DateTime<Utc>, limit: usize) -> Result<Vec<PriceChange>, Error> {
+        let rows = self
+            .query(
+                "SELECT * FROM ProductPrices
+                WHERE applied_at IS NULL AND effective_from <= $1
+                ORDER BY effective_from LIMIT $2",
+                &[&now, &(limit as i64)],
+            )
+            .await?;
+        rows.iter().map(price_change).collect()
+    }
+
+    async fn apply(&self, id: Uuid, at: DateTime<Utc>) -> Result<(), Error> {
+        self.query(
+            "UPDATE ProductPrices SET applied_at = $2 WHERE id = $1",
+            &[&id, &at],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<(), Error> {
+        self.query(
+            "DELETE FROM ProductPrices WHERE id = $1 AND applied_at IS NULL",
+            &[&id],
+        )
+        .await?;
+        Ok(())
+    }
+}
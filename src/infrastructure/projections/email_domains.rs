@@ -0,0 +1,105 @@
+use super::Projection;
+use crate::domain::interfaces::Aggregate;
+use crate::domain::models::User;
+use crate::infrastructure::event_store::RecordedEvent;
+use axum::async_trait;
+use deadpool_postgres::Transaction;
+use std::error;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Number of users per email domain. The domain of every user is kept as
+/// well, so a changed or deleted address moves the user out of its old
+/// domain.
+pub struct EmailDomains;
+
+impl EmailDomains {
+    async fn count(transaction: &Transaction<'_>, domain: &str, delta: i64) -> Result<(), Error> {
+        transaction
+            .execute(
+                "INSERT INTO EmailDomainCounts (domain, users) VALUES ($1, $2)
+                ON CONFLICT (domain) DO UPDATE SET users = EmailDomainCounts.users + $2",
+                &[&domain, &delta],
+            )
+            .await?;
+        transaction
+            .execute(
+                "DELETE FROM EmailDomainCounts WHERE domain = $1 AND users <= 0",
+                &[&domain],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Projection for EmailDomains {
+    fn name(&self) -> &'static str {
+        "email-domains"
+    }
+
+    async fn reset(&self, transaction: &Transaction<'_>) -> Result<(), Error> {
+        transaction
+            .execute("DELETE FROM UserEmailDomains", &[])
+            .await?;
+        transaction
+            .execute("DELETE FROM EmailDomainCounts", &[])
+            .await?;
+        Ok(())
+    }
+
+    async fn apply(
+        &self,
+        transaction: &Transaction<'_>,
+        event: &RecordedEvent,
+    ) -> Result<(), Error> {
+        if event.stream_type != User::STREAM {
+            return Ok(());
+        }
+        let domain = match (&event.data, event.event_type.as_str()) {
+            (Some(data), "created" | "updated" | "restored") => {
+                let user: User = serde_json::from_value(data.clone())?;
+                let domain = match user.email.rsplit_once('@') {
+                    Some((_, domain)) => domain.to_lowercase(),
+                    None => String::new(),
+                };
+                Some(domain)
+            }
+            _ => None,
+        };
+        let previous: Option<String> = transaction
+            .query_opt(
+                "SELECT domain FROM UserEmailDomains WHERE user_id = $1",
+                &[&event.stream_id],
+            )
+            .await?
+            .map(|row| row.get("domain"));
+        if previous == domain {
+            return Ok(());
+        }
+        if let Some(previous) = &previous {
+            Self::count(transaction, previous, -1).await?;
+        }
+        match &domain {
+            Some(domain) => {
+                transaction
+                    .execute(
+                        "INSERT INTO UserEmailDomains (user_id, domain) VALUES ($1, $2)
+                        ON CONFLICT (user_id) DO UPDATE SET domain = $2",
+                        &[&event.stream_id, domain],
+                    )
+                    .await?;
+                Self::count(transaction, domain, 1).await?;
+            }
+            None => {
+                transaction
+                    .execute(
+                        "DELETE FROM UserEmailDomains WHERE user_id = $1",
+                        &[&event.stream_id],
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
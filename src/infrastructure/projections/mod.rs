@@ -0,0 +1,218 @@
+mod email_domains;
+mod product_catalog;
+mod read_model;
+
+pub use email_domains::EmailDomains;
+pub use product_catalog::ProductCatalog;
+pub use read_model::{CatalogQuery, ReadModel};
+
+use crate::infrastructure::event_store::{EventStore, LogPosition, RecordedEvent};
+use axum::async_trait;
+use deadpool_postgres::{Pool, Transaction};
+use log::error;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Where the projector reads the events from.
+#[derive(Clone, Copy)]
+pub enum EventLog {
+    /// The `Events` table of the event-sourced storage.
+    Events,
+    /// The versions in the history tables of the crud storage.
+    History,
+}
+
+impl EventLog {
+    fn relation(&self) -> &'static str {
+        match self {
+            EventLog::Events => "Events",
+            EventLog::History => "HistoryLog",
+        }
+    }
+}
+
+/// A denormalized read table maintained from the event log.
+#[async_trait]
+pub trait Projection: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Removes everything the projection has written.
+    async fn reset(&self, transaction: &Transaction<'_>) -> Result<(), Error>;
+
+    async fn apply(
+        &self,
+        transaction: &Transaction<'_>,
+        event: &RecordedEvent,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Serialize)]
+pub struct ProjectionStatus {
+    pub name: String,
+    pub checkpoint: String,
+    /// Events recorded after the checkpoint.
+    pub lag: i64,
+    pub last_error: Option<String>,
+}
+
+/// Feeds the event log to the projections. Each projection keeps its own
+/// checkpoint, which is saved in the transaction that updates its tables, so
+/// every event is applied exactly once.
+pub struct Projector {
+    pool: Pool,
+    log: EventLog,
+    projections: Vec<Box<dyn Projection>>,
+    batch_size: i64,
+    errors: Mutex<HashMap<&'static str, String>>,
+}
+
+impl Projector {
+    pub fn new(
+        pool: Pool,
+        log: EventLog,
+        projections: Vec<Box<dyn Projection>>,
+        batch_size: i64,
+    ) -> Projector {
+        Projector {
+            pool,
+            log,
+            projections,
+            batch_size,
+            errors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let projector = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let mut busy = false;
+                for projection in &projector.projections {
+                    match projector.project(projection.as_ref(), false).await {
+                        Ok(count) => {
+                            busy |= count as i64 == projector.batch_size;
+                            projector.errors.lock().unwrap().remove(projection.name());
+                        }
+                        Err(err) => {
+                            error!("Projection {} failed: {}", projection.name(), err);
+                            projector
+                                .errors
+                                .lock()
+                                .unwrap()
+                                .insert(projection.name(), err.to_string());
+                        }
+                    }
+                }
+                if !busy {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        });
+    }
+
+    pub async fn status(&self) -> Result<Vec<ProjectionStatus>, Error> {
+        let connection = self.pool.get().await?;
+        let mut statuses = Vec::with_capacity(self.projections.len());
+        for projection in &self.projections {
+            let row = connection
+                .query_opt(
+                    "SELECT position FROM Checkpoints WHERE name = $1",
+                    &[&self.checkpoint(projection.as_ref())],
+                )
+                .await?;
+            let checkpoint = match row {
+                Some(row) => row.get::<_, &str>("position").parse()?,
+                None => LogPosition::default(),
+            };
+            statuses.push(ProjectionStatus {
+                name: projection.name().to_string(),
+                lag: EventStore::count_after(&connection, self.log.relation(), &checkpoint).await?,
+                checkpoint: checkpoint.to_string(),
+                last_error: self.errors.lock().unwrap().get(projection.name()).cloned(),
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Clears the projection and applies the whole event log to it in one
+    /// transaction, so readers keep seeing the old contents until it is done.
+    /// Returns the number of applied events.
+    pub async fn rebuild(&self, name: &str) -> Result<usize, Error> {
+        let projection = self
+            .projections
+            .iter()
+            .find(|projection| projection.name() == name)
+            .ok_or(io::Error::from(ErrorKind::NotFound))?;
+        self.project(projection.as_ref(), true).await
+    }
+
+    /// Positions of the two logs are not comparable, each has its own
+    /// checkpoints.
+    fn checkpoint(&self, projection: &dyn Projection) -> String {
+        match self.log {
+            EventLog::Events => format!("projection:{}", projection.name()),
+            EventLog::History => format!("projection:history:{}", projection.name()),
+        }
+    }
+
+    async fn project(&self, projection: &dyn Projection, rebuild: bool) -> Result<usize, Error> {
+        let name = self.checkpoint(projection);
+        let mut connection = self.pool.get().await?;
+        let transaction = connection.transaction().await?;
+        // A projection without a checkpoint starts over, its tables may hold
+        // what was projected from the other log.
+        let fresh = transaction
+            .execute(
+                "INSERT INTO Checkpoints (name, position) VALUES ($1, $2)
+                ON CONFLICT (name) DO NOTHING",
+                &[&name, &LogPosition::default().to_string()],
+            )
+            .await?
+            == 1;
+        let rebuild = rebuild || fresh;
+        let row = transaction
+            .query_one(
+                "SELECT position FROM Checkpoints WHERE name = $1 FOR UPDATE",
+                &[&name],
+            )
+            .await?;
+        let mut checkpoint: LogPosition = row.get::<_, &str>("position").parse()?;
+        if rebuild {
+            projection.reset(&transaction).await?;
+            checkpoint = LogPosition::default();
+        }
+        let mut applied = 0;
+        loop {
+            let events = EventStore::read_log(
+                &transaction,
+                self.log.relation(),
+                &checkpoint,
+                self.batch_size,
+            )
+            .await?;
+            for event in &events {
+                projection.apply(&transaction, event).await?;
+                checkpoint = event.log_position.clone();
+            }
+            applied += events.len();
+            if !rebuild || (events.len() as i64) < self.batch_size {
+                break;
+            }
+        }
+        transaction
+            .execute(
+                "UPDATE Checkpoints SET position = $2, updated_at = now() WHERE name = $1",
+                &[&name, &checkpoint.to_string()],
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(applied)
+    }
+}
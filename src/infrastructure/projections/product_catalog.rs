@@ -0,0 +1,71 @@
+use super::Projection;
+use crate::domain::interfaces::Aggregate;
+use crate::domain::models::Product;
+use crate::infrastructure::event_store::RecordedEvent;
+use axum::async_trait;
+use deadpool_postgres::Transaction;
+use std::error;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Catalog view of the products with the previous price, the number of
+/// revisions and the time the product was listed.
+pub struct ProductCatalog;
+
+#[async_trait]
+impl Projection for ProductCatalog {
+    fn name(&self) -> &'static str {
+        "product-catalog"
+    }
+
+    async fn reset(&self, transaction: &Transaction<'_>) -> Result<(), Error> {
+        transaction
+            .execute("DELETE FROM ProductCatalog", &[])
+            .await?;
+        Ok(())
+    }
+
+    async fn apply(
+        &self,
+        transaction: &Transaction<'_>,
+        event: &RecordedEvent,
+    ) -> Result<(), Error> {
+        if event.stream_type != Product::STREAM {
+            return Ok(());
+        }
+        let (Some(data), "created" | "updated" | "restored") =
+            (&event.data, event.event_type.as_str())
+        else {
+            transaction
+                .execute(
+                    "DELETE FROM ProductCatalog WHERE id = $1",
+                    &[&event.stream_id],
+                )
+                .await?;
+            return Ok(());
+        };
+        let product: Product = serde_json::from_value(data.clone())?;
+        transaction
+            .execute(
+                "INSERT INTO ProductCatalog
+                (id, name, search_name, price, previous_price, revision, listed_at, changed_at)
+                VALUES ($1, $2, lower($2), $3, NULL, 1, $4, $4)
+                ON CONFLICT (id) DO UPDATE SET
+                name = $2,
+                search_name = lower($2),
+                price = $3,
+                previous_price = CASE WHEN ProductCatalog.price <> $3
+                    THEN ProductCatalog.price ELSE ProductCatalog.previous_price END,
+                revision = ProductCatalog.revision + 1,
+                changed_at = $4",
+                &[
+                    &event.stream_id,
+                    &product.name,
+                    &product.price,
+                    &event.recorded_at,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use crate::domain::models::Money;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use std::error;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+#[derive(Serialize)]
+pub struct CatalogEntry {
+    pub id: Uuid,
+    pub name: String,
+    pub price: Money,
+    pub previous_price: Option<Money>,
+    pub revision: i64,
+    pub listed_at: DateTime<Utc>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl TryFrom<&Row> for CatalogEntry {
+    type Error = Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        Ok(CatalogEntry {
+            id: Uuid::parse_str(row.get("id"))?,
+            name: row.get("name"),
+            price: row.get("price"),
+            previous_price: row.get("previous_price"),
+            revision: row.get("revision"),
+            listed_at: row.get("listed_at"),
+            changed_at: row.get("changed_at"),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct DomainCount {
+    pub domain: String,
+    pub users: i64,
+}
+
+#[derive(Deserialize)]
+pub struct CatalogQuery {
+    /// Case insensitive substring of the product name.
+    pub q: Option<String>,
+    pub after: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// Queries over the tables maintained by the projections.
+pub struct ReadModel {
+    pool: Pool,
+}
+
+impl ReadModel {
+    const MAX_LIMIT: i64 = 1000;
+
+    pub fn new(pool: Pool) -> ReadModel {
+        ReadModel { pool }
+    }
+
+    pub async fn products(&self, query: &CatalogQuery) -> Result<Vec<CatalogEntry>, Error> {
+        let connection = self.pool.get().await?;
+        let search = query.q.as_ref().map(|q| format!("%{}%", q.to_lowercase()));
+        let after = query.after.map(|id| id.to_string());
+        let limit = query.limit.unwrap_or(100).clamp(1, Self::MAX_LIMIT);
+        let params: [&(dyn ToSql + Sync); 3] = [&search, &after, &limit];
+        let rows = connection
+            .query(
+                "SELECT * FROM ProductCatalog
+                WHERE ($1::TEXT IS NULL OR search_name LIKE $1)
+                AND ($2::TEXT IS NULL OR id > $2)
+                ORDER BY id LIMIT $3",
+                &params,
+            )
+            .await?;
+        rows.iter().map(CatalogEntry::try_from).collect()
+    }
+
+    pub async fn product(&self, id: Uuid) -> Result<Option<CatalogEntry>, Error> {
+        let connection = self.pool.get().await?;
+        let row = connection
+            .query_opt(
+                "SELECT * FROM ProductCatalog WHERE id = $1",
+                &[&id.to_string()],
+            )
+            .await?;
+        row.as_ref().map(CatalogEntry::try_from).transpose()
+    }
+
+    pub async fn email_domains(&self) -> Result<Vec<DomainCount>, Error> {
+        let connection = self.pool.get().await?;
+        let rows = connection
+            .query(
+                "SELECT domain, users FROM EmailDomainCounts ORDER BY users DESC, domain",
+                &[],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| DomainCount {
+                domain: row.get("domain"),
+                users: row.get("users"),
+            })
+            .collect())
+    }
+}
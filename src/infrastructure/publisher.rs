@@ -0,0 +1,363 @@
+use crate::domain::interfaces;
+use crate::domain::interfaces::{Identifiable, MessageBroker};
+use crate::domain::models::Envelope;
+use crate::infrastructure::metrics::Exposition;
+use axum::async_trait;
+use log::{error, warn};
+use serde::Serialize;
+use std::error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+type Broker = Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Error> + Send + Sync>;
+
+const SPILL_FILE: &str = "spill.jsonl";
+const DRAINING_FILE: &str = "spill.jsonl.draining";
+/// Deliveries of an event before it is spilled, waiting twice as long after
+/// every failure.
+const DELIVERY_ATTEMPTS: u32 = 5;
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// What `QueuedBroker` does with an event when its queue is full.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backpressure {
+    /// Wait for a free slot, the request is delayed until a worker catches up.
+    Block,
+    /// Fail immediately with `PublishRejected`. The entity is already persisted at this point.
+    Reject,
+    /// Append the event to a file in the spill directory, re-enqueued once the queue drains.
+    Spill,
+}
+
+impl FromStr for Backpressure {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "block" => Ok(Backpressure::Block),
+            "reject" => Ok(Backpressure::Reject),
+            "spill" => Ok(Backpressure::Spill),
+            other => Err(format!("unknown backpressure mode: {other}").into()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PublishRejected;
+
+impl fmt::Display for PublishRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "publish queue is full")
+    }
+}
+
+impl error::Error for PublishRejected {}
+
+#[derive(Default)]
+struct Counters {
+    published: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    rejected: AtomicU64,
+    spilled: AtomicU64,
+}
+
+#[derive(Serialize)]
+pub struct PublisherStats {
+    pub backpressure: Backpressure,
+    pub capacity: usize,
+    pub depth: usize,
+    pub published: u64,
+    /// Events spilled after their deliveries failed.
+    pub failed: u64,
+    pub dropped: u64,
+    pub rejected: u64,
+    pub spilled: u64,
+}
+
+/// Message broker that enqueues events into a bounded in-process queue and
+/// returns immediately, leaving delivery to background workers. Events whose
+/// deliveries keep failing are spilled and re-enqueued later.
+pub struct QueuedBroker {
+    sender: RwLock<Option<Sender<Envelope>>>,
+    capacity: usize,
+    backpressure: Backpressure,
+    spill_dir: PathBuf,
+    spill_lock: Mutex<()>,
+    counters: Counters,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl QueuedBroker {
+    pub async fn new(
+        inner: Broker,
+        capacity: usize,
+        workers: usize,
+        backpressure: Backpressure,
+        spill_dir: PathBuf,
+    ) -> Result<Arc<QueuedBroker>, Error> {
+        if capacity == 0 {
+            return Err("publish queue capacity must be positive".into());
+        }
+        fs::create_dir_all(&spill_dir).await?;
+        let (sender, receiver) = mpsc::channel(capacity);
+        let broker = Arc::new(QueuedBroker {
+            sender: RwLock::new(Some(sender)),
+            capacity,
+            backpressure,
+            spill_dir,
+            spill_lock: Mutex::new(()),
+            counters: Counters::default(),
+            workers: Mutex::new(Vec::new()),
+        });
+        let receiver = Arc::new(Mutex::new(receiver));
+        let mut handles = Vec::with_capacity(workers);
+        for _ in 0..workers.max(1) {
+            handles.push(tokio::spawn(Self::work(
+                broker.clone(),
+                inner.clone(),
+                receiver.clone(),
+            )));
+        }
+        tokio::spawn(Self::drain_spill(Arc::downgrade(&broker)));
+        *broker.workers.lock().await = handles;
+        Ok(broker)
+    }
+
+    pub fn stats(&self) -> PublisherStats {
+        let depth = match self.sender() {
+            Some(sender) => sender.max_capacity() - sender.capacity(),
+            None => 0,
+        };
+        PublisherStats {
+            backpressure: self.backpressure,
+            capacity: self.capacity,
+            depth,
+            published: self.counters.published.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            rejected: self.counters.rejected.load(Ordering::Relaxed),
+            spilled: self.counters.spilled.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn write_metrics(&self, exposition: &mut Exposition) {
+        let stats = self.stats();
+        exposition.gauge(
+            "mesgmon_publish_queue_depth",
+            "Events waiting in the publish queue.",
+            &[(vec![], stats.depth as f64)],
+        );
+        exposition.gauge(
+            "mesgmon_publish_queue_capacity",
+            "Capacity of the publish queue.",
+            &[(vec![], stats.capacity as f64)],
+        );
+        exposition.counter(
+            "mesgmon_publish_events_total",
+            "Queued events by outcome.",
+            &[
+                (
+                    vec![("outcome", "published".to_string())],
+                    stats.published as f64,
+                ),
+                (vec![("outcome", "failed".to_string())], stats.failed as f64),
+                (
+                    vec![("outcome", "dropped".to_string())],
+                    stats.dropped as f64,
+                ),
+                (
+                    vec![("outcome", "rejected".to_string())],
+                    stats.rejected as f64,
+                ),
+                (
+                    vec![("outcome", "spilled".to_string())],
+                    stats.spilled as f64,
+                ),
+            ],
+        );
+    }
+
+    /// Stops accepting events and waits until the workers have delivered
+    /// everything left in the queue. Spilled events stay on disk for the next start.
+    pub async fn shutdown(&self) {
+        self.sender.write().unwrap().take();
+        for handle in self.workers.lock().await.drain(..) {
+            if let Err(err) = handle.await {
+                error!("Publisher worker failed: {}", err);
+            }
+        }
+    }
+
+    fn sender(&self) -> Option<Sender<Envelope>> {
+        self.sender.read().unwrap().clone()
+    }
+
+    async fn enqueue(&self, envelope: Envelope) -> Result<(), Error> {
+        let sender = self.sender().ok_or("publisher is shut down")?;
+        match self.backpressure {
+            Backpressure::Block => sender.send(envelope).await.map_err(|err| err.to_string())?,
+            Backpressure::Reject => match sender.try_send(envelope) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    self.counters.rejected.fetch_add(1, Ordering::Relaxed);
+                    return Err(PublishRejected.into());
+                }
+                Err(TrySendError::Closed(_)) => return Err("publisher is shut down".into()),
+            },
+            Backpressure::Spill => match sender.try_send(envelope) {
+                Ok(()) => {}
+                Err(TrySendError::Full(envelope)) => self.spill(&envelope).await?,
+                Err(TrySendError::Closed(_)) => return Err("publisher is shut down".into()),
+            },
+        }
+        Ok(())
+    }
+
+    async fn spill(&self, envelope: &Envelope) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(envelope)?;
+        line.push(b'\n');
+        let _guard = self.spill_lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.spill_dir.join(SPILL_FILE))
+            .await?;
+        file.write_all(&line).await?;
+        self.counters.spilled.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn work(self: Arc<Self>, inner: Broker, receiver: Arc<Mutex<Receiver<Envelope>>>) {
+        loop {
+            let envelope = receiver.lock().await.recv().await;
+            let Some(envelope) = envelope else {
+                break;
+            };
+            let Err(err) = Self::deliver(&inner, &envelope).await else {
+                self.counters.published.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            error!("Failed to deliver queued event, spilling it: {}", err);
+            match self.spill(&envelope).await {
+                Ok(()) => {
+                    self.counters.failed.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(err) => {
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    error!("Dropping undeliverable event: {}", err);
+                }
+            }
+        }
+    }
+
+    /// Publishes `envelope`, retrying with backoff. The error of the last
+    /// attempt is returned.
+    async fn deliver(inner: &Broker, envelope: &Envelope) -> Result<(), Error> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match inner.publish(envelope.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt == DELIVERY_ATTEMPTS => return Err(err),
+                Err(err) => warn!("Delivery attempt {} failed: {}", attempt, err),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Moves spilled events back into the queue whenever it is at most half full.
+    async fn drain_spill(broker: std::sync::Weak<QueuedBroker>) {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let Some(broker) = broker.upgrade() else {
+                break;
+            };
+            let Some(sender) = broker.sender() else {
+                break;
+            };
+            if sender.capacity() < broker.capacity / 2 {
+                continue;
+            }
+            if let Err(err) = broker.requeue_spilled(&sender).await {
+                warn!("Failed to drain spilled events: {}", err);
+            }
+        }
+    }
+
+    async fn requeue_spilled(&self, sender: &Sender<Envelope>) -> Result<(), Error> {
+        let draining = self.spill_dir.join(DRAINING_FILE);
+        if fs::metadata(&draining).await.is_err() {
+            let _guard = self.spill_lock.lock().await;
+            let spill = self.spill_dir.join(SPILL_FILE);
+            if fs::metadata(&spill).await.is_err() {
+                return Ok(());
+            }
+            fs::rename(&spill, &draining).await?;
+        }
+        let content = fs::read_to_string(&draining).await?;
+        let lines: Vec<&str> = content.lines().filter(|line| !line.is_empty()).collect();
+        for (sent, line) in lines.iter().enumerate() {
+            match serde_json::from_str::<Envelope>(line) {
+                Ok(envelope) => {
+                    if let Err(err) = sender.send(envelope).await {
+                        // Keeps only the events that did not reach the queue.
+                        let mut unsent = lines[sent..].join("\n");
+                        unsent.push('\n');
+                        fs::write(&draining, unsent).await?;
+                        return Err(err.to_string().into());
+                    }
+                }
+                Err(err) => {
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    error!("Dropping unreadable spilled event: {}", err);
+                }
+            }
+        }
+        fs::remove_file(&draining).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M: Identifiable + Sync + ?Sized> interfaces::MessageBroker<M> for QueuedBroker {
+    type Error = Error;
+
+    async fn send(&self, topic: &str, action: &str, message: &M) -> Result<(), Self::Error> {
+        self.enqueue(Envelope::new(topic, action, message)).await
+    }
+
+    async fn publish(&self, envelope: Envelope) -> Result<(), Self::Error> {
+        self.enqueue(envelope).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backpressure_modes_parse_from_their_names() {
+        assert!(matches!("block".parse(), Ok(Backpressure::Block)));
+        assert!(matches!("reject".parse(), Ok(Backpressure::Reject)));
+        assert!(matches!("spill".parse(), Ok(Backpressure::Spill)));
+        let error = "Block".parse::<Backpressure>().err().unwrap();
+        assert_eq!(error.to_string(), "unknown backpressure mode: Block");
+        assert!("".parse::<Backpressure>().is_err());
+    }
+}
@@ -1,6 +1,9 @@
+use crate::domain::dto::Scan;
 use crate::domain::interfaces;
 use crate::domain::interfaces::Database;
+use crate::domain::models::{Change, Version};
 use axum::async_trait;
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
 type Error = Box<dyn std::error::Error + Sync + Send>;
@@ -13,11 +16,15 @@ impl<T: Send + Sync> interfaces::Repository<T> for Repository<T> {
     type Error = Error;
     type Id = Uuid;
 
-    async fn add(&self, item: T) -> Result<(), Self::Error> {
+    async fn add(&self, item: T) -> Result<T, Self::Error> {
         self.storage.add(item).await
     }
 
-    async fn remove(&self, id: Self::Id) -> Result<(), Self::Error> {
+    async fn add_all(&self, items: Vec<T>) -> Result<Vec<T>, Self::Error> {
+        self.storage.add_all(items).await
+    }
+
+    async fn remove(&self, id: Self::Id) -> Result<bool, Self::Error> {
         self.storage.delete(id).await
     }
 
@@ -25,7 +32,43 @@ impl<T: Send + Sync> interfaces::Repository<T> for Repository<T> {
         self.storage.get(id).await
     }
 
-    async fn update(&self, item: T) -> Result<(), Self::Error> {
+    async fn update(&self, item: T) -> Result<Option<T>, Self::Error> {
         self.storage.update(item).await
     }
+
+    async fn update_fields(&self, item: T, fields: &[String]) -> Result<Option<T>, Self::Error> {
+        self.storage.update_fields(item, fields).await
+    }
+
+    async fn upsert(&self, item: T) -> Result<Option<Change<T>>, Self::Error> {
+        self.storage.upsert(item).await
+    }
+
+    async fn scan(&self, scan: &Scan) -> Result<Vec<T>, Self::Error> {
+        self.storage.scan(scan).await
+    }
+
+    async fn get_as_of(
+        &self,
+        id: Self::Id,
+        as_of: DateTime<Utc>,
+    ) -> Result<Option<T>, Self::Error> {
+        self.storage.get_as_of(id, as_of).await
+    }
+
+    async fn history(&self, id: Self::Id) -> Result<Vec<Version<T>>, Self::Error> {
+        self.storage.history(id).await
+    }
+
+    async fn restore(&self, id: Self::Id) -> Result<bool, Self::Error> {
+        self.storage.restore(id).await
+    }
+
+    async fn purge(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Self::Id>, Self::Error> {
+        self.storage.purge(deleted_before, limit).await
+    }
 }
@@ -0,0 +1,66 @@
+use crate::domain::models::{Money, Product, User};
+use chrono::{DateTime, Utc};
+use mesgmon_derive::Table;
+use uuid::Uuid;
+
+/// Row of `Users`, maps `User` to the table.
+#[derive(Table)]
+#[table(name = "Users", history = "UserHistory", entity = "User")]
+struct UserRow {
+    id: Uuid,
+    name: String,
+    email: String,
+    #[column(generated)]
+    created_at: DateTime<Utc>,
+    #[column(generated)]
+    updated_at: DateTime<Utc>,
+}
+
+/// Row of `Products`, maps `Product` to the table.
+#[derive(Table)]
+#[table(name = "Products", history = "ProductHistory", entity = "Product")]
+struct ProductRow {
+    id: Uuid,
+    name: String,
+    price: Money,
+    #[column(generated)]
+    created_at: DateTime<Utc>,
+    #[column(generated)]
+    updated_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::Table;
+
+    #[test]
+    fn rows_map_their_entities_to_the_tables() {
+        assert_eq!(User::NAME, "Users");
+        assert_eq!(User::HISTORY, "UserHistory");
+        assert_eq!(User::COLUMNS, ["id", "name", "email"]);
+        assert_eq!(User::GENERATED, ["created_at", "updated_at"]);
+        assert_eq!(Product::NAME, "Products");
+        assert_eq!(Product::HISTORY, "ProductHistory");
+        assert_eq!(Product::COLUMNS, ["id", "name", "price"]);
+        assert_eq!(Product::GENERATED, ["created_at", "updated_at"]);
+    }
+
+    #[test]
+    fn params_follow_the_columns() {
+        let user = User {
+            id: Uuid::nil(),
+            name: "test".to_string(),
+            email: "test@example.com".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let params: Vec<_> = user
+            .to_params()
+            .iter()
+            .map(|param| format!("{param:?}"))
+            .collect();
+        let id = format!("{:?}", Uuid::nil());
+        assert_eq!(params, [id.as_str(), "\"test\"", "\"test@example.com\""]);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::domain::interfaces::Identifiable;
+use serde::de::DeserializeOwned;
+use std::error;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+/// Maps an entity to a table with a `deleted_at` column and a history table
+/// maintained by `record_history()`. Usually derived with
+/// `mesgmon_derive::Table` on a row struct in `rows`, which keeps the domain
+/// models free of storage code.
+pub trait Table: Identifiable + DeserializeOwned + Send + Sync + Sized + 'static {
+    const NAME: &'static str;
+    const HISTORY: &'static str;
+    /// Columns written by inserts and updates, the primary key first.
+    const COLUMNS: &'static [&'static str];
+    /// Columns filled in by the database, returned by every write.
+    const GENERATED: &'static [&'static str];
+
+    /// Values of `COLUMNS`, in the same order.
+    fn to_params(&self) -> Vec<Box<dyn ToSql + Send + Sync>>;
+
+    fn from_row(row: &Row) -> Result<Self, Error>;
+}
+
+/// SQL of the statements the generic `Database` implementation runs against a
+/// table, generated once per table.
+pub struct Statements {
+    pub insert: String,
+    pub update: String,
+    pub upsert: String,
+    pub delete: String,
+    pub get: String,
+    pub scan: String,
+    pub get_version: String,
+    pub history: String,
+    pub restore: String,
+    pub purge: String,
+}
+
+impl Statements {
+    pub fn new<T: Table>() -> Statements {
+        let table = T::NAME;
+        let history = T::HISTORY;
+        let key = T::COLUMNS[0];
+        let columns = returned::<T>();
+        let placeholders = placeholders(1, T::COLUMNS.len());
+        let assignments = T::COLUMNS
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(index, column)| format!("{column} = ${}", index + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Statements {
+            insert: format!(
+                "INSERT INTO {table} ({}) VALUES ({placeholders}) RETURNING {columns}",
+                T::COLUMNS.join(", ")
+            ),
+            update: format!(
+                "UPDATE {table} SET {assignments} WHERE {key} = $1 AND deleted_at IS NULL
+                RETURNING {columns}"
+            ),
+            upsert: format!(
+                "INSERT INTO {table} ({}) VALUES ({placeholders})
+                ON CONFLICT ({key}) DO UPDATE SET {} WHERE {table}.deleted_at IS NULL
+                RETURNING {columns}, xmax = 0 AS inserted",
+                T::COLUMNS.join(", "),
+                T::COLUMNS[1..]
+                    .iter()
+                    .map(|column| format!("{column} = EXCLUDED.{column}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            delete: format!(
+                "UPDATE {table} SET deleted_at = now() WHERE {key} = $1 AND deleted_at IS NULL
+                RETURNING {key}"
+            ),
+            get: format!("SELECT {columns} FROM {table} WHERE {key} = $1 AND deleted_at IS NULL"),
+            scan: format!(
+                "SELECT {columns} FROM {table}
+                WHERE deleted_at IS NULL
+                AND ($1::UUID IS NULL OR {key} > $1)
+                AND ($2::UUID[] IS NULL OR {key} = ANY($2))
+                AND ($3::TIMESTAMPTZ IS NULL OR updated_at >= $3)
+                ORDER BY {key} LIMIT $4"
+            ),
+            get_version: format!(
+                "SELECT data, valid_from, (
+                    SELECT max(valid_from) FROM {history} created
+                    WHERE created.id = $1 AND created.operation = 'create'
+                    AND created.valid_from <= $2
+                ) AS created_at
+                FROM {history}
+                WHERE id = $1 AND valid_from <= $2 AND (valid_to IS NULL OR valid_to > $2)"
+            ),
+            history: format!(
+                "SELECT version, operation, data, principal, valid_from, valid_to,
+                max(valid_from) FILTER (WHERE operation = 'create')
+                    OVER (ORDER BY version) AS created_at
+                FROM {history} WHERE id = $1 ORDER BY version"
+            ),
+            restore: format!(
+                "UPDATE {table} SET deleted_at = NULL
+                WHERE {key} = $1 AND deleted_at IS NOT NULL RETURNING {key}"
+            ),
+            purge: format!(
+                "DELETE FROM {table} WHERE {key} IN (
+                    SELECT {key} FROM {table} WHERE deleted_at < $1 LIMIT $2
+                ) RETURNING {key}"
+            ),
+        }
+    }
+
+    /// Update of the `fields` that are among `COLUMNS`, with the indexes of
+    /// the values of `to_params` it takes. `None` if no field is a column.
+    pub fn update_fields<T: Table>(fields: &[String]) -> Option<(String, Vec<usize>)> {
+        let changed = |index: &usize| fields.iter().any(|field| field == T::COLUMNS[*index]);
+        let mut indexes = vec![0];
+        indexes.extend((1..T::COLUMNS.len()).filter(changed));
+        if indexes.len() == 1 {
+            return None;
+        }
+        let assignments = indexes
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(param, &index)| format!("{} = ${}", T::COLUMNS[index], param + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let statement = format!(
+            "UPDATE {} SET {assignments} WHERE {} = $1 AND deleted_at IS NULL RETURNING {}",
+            T::NAME,
+            T::COLUMNS[0],
+            returned::<T>()
+        );
+        Some((statement, indexes))
+    }
+
+    /// Insert of `rows` items at once, taking the values of each item in turn.
+    pub fn insert_all<T: Table>(rows: usize) -> String {
+        let width = T::COLUMNS.len();
+        let values = (0..rows)
+            .map(|row| format!("({})", placeholders(row * width + 1, width)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "INSERT INTO {} ({}) VALUES {values} RETURNING {}",
+            T::NAME,
+            T::COLUMNS.join(", "),
+            returned::<T>()
+        )
+    }
+}
+
+fn returned<T: Table>() -> String {
+    T::COLUMNS
+        .iter()
+        .chain(T::GENERATED)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn placeholders(first: usize, count: usize) -> String {
+    (first..first + count)
+        .map(|index| format!("${index}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::User;
+
+    fn squash(sql: &str) -> String {
+        sql.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn statements_write_the_columns_and_return_the_generated_ones() {
+        let statements = Statements::new::<User>();
+        assert_eq!(
+            statements.insert,
+            "INSERT INTO Users (id, name, email) VALUES ($1, $2, $3) \
+            RETURNING id, name, email, created_at, updated_at"
+        );
+        assert_eq!(
+            squash(&statements.update),
+            "UPDATE Users SET name = $2, email = $3 WHERE id = $1 AND deleted_at IS NULL \
+            RETURNING id, name, email, created_at, updated_at"
+        );
+        assert_eq!(
+            squash(&statements.upsert),
+            "INSERT INTO Users (id, name, email) VALUES ($1, $2, $3) \
+            ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, email = EXCLUDED.email \
+            WHERE Users.deleted_at IS NULL \
+            RETURNING id, name, email, created_at, updated_at, xmax = 0 AS inserted"
+        );
+        assert_eq!(
+            squash(&statements.purge),
+            "DELETE FROM Users WHERE id IN ( \
+            SELECT id FROM Users WHERE deleted_at < $1 LIMIT $2 ) RETURNING id"
+        );
+        let history = squash(&statements.history);
+        assert!(history.ends_with("FROM UserHistory WHERE id = $1 ORDER BY version"));
+    }
+
+    #[test]
+    fn field_updates_take_only_the_changed_columns() {
+        let fields = ["email".to_string(), "role".to_string()];
+        let (statement, indexes) = Statements::update_fields::<User>(&fields).unwrap();
+        assert_eq!(
+            statement,
+            "UPDATE Users SET email = $2 WHERE id = $1 AND deleted_at IS NULL \
+            RETURNING id, name, email, created_at, updated_at"
+        );
+        assert_eq!(indexes, [0, 2]);
+        assert!(Statements::update_fields::<User>(&["id".to_string()]).is_none());
+        assert!(Statements::update_fields::<User>(&[]).is_none());
+    }
+
+    #[test]
+    fn bulk_inserts_number_the_values_of_each_row() {
+        assert_eq!(
+            Statements::insert_all::<User>(2),
+            "INSERT INTO Users (id, name, email) VALUES ($1, $2, $3), ($4, $5, $6) \
+            RETURNING id, name, email, created_at, updated_at"
+        );
+    }
+}
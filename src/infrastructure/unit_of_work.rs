@@ -0,0 +1,191 @@
+use crate::domain::interfaces;
+use crate::domain::interfaces::{Identifiable, MessageBroker};
+use crate::domain::models::Envelope;
+use crate::infrastructure::postgres::{Executor, Postgres};
+use crate::infrastructure::table::{Statements, Table};
+use crate::infrastructure::{PostgresPriceTimeline, Repository};
+use axum::async_trait;
+use deadpool_postgres::Object;
+use std::error;
+use std::sync::{Arc, Mutex};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+type Broker = Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Error> + Send + Sync>;
+
+/// Begins units of work on a connection of `postgres`, publishing their events
+/// to `broker` once they commit.
+pub struct PostgresUnitOfWork {
+    postgres: Arc<Postgres>,
+    broker: Broker,
+}
+
+impl PostgresUnitOfWork {
+    pub fn new(postgres: Arc<Postgres>, broker: Broker) -> PostgresUnitOfWork {
+        PostgresUnitOfWork { postgres, broker }
+    }
+}
+
+#[async_trait]
+impl interfaces::UnitOfWorkFactory for PostgresUnitOfWork {
+    type Unit = PostgresUnit;
+    type Error = Error;
+
+    async fn begin(&self) -> Result<PostgresUnit, Self::Error> {
+        let connection = self.postgres.pool().get().await?;
+        connection.batch_execute("BEGIN").await?;
+        Postgres::attribute(&**connection).await?;
+        Ok(PostgresUnit(Arc::new(Unit {
+            postgres: self.postgres.clone(),
+            connection: tokio::sync::Mutex::new(Some(connection)),
+            pending: Arc::new(Pending::default()),
+            broker: self.broker.clone(),
+        })))
+    }
+}
+
+/// A transaction on one connection shared by the repositories of the unit.
+/// The transaction is rolled back when the unit is dropped unfinished.
+#[derive(Clone)]
+pub struct PostgresUnit(Arc<Unit>);
+
+struct Unit {
+    postgres: Arc<Postgres>,
+    /// Taken when the unit commits or rolls back.
+    connection: tokio::sync::Mutex<Option<Object>>,
+    pending: Arc<Pending>,
+    broker: Broker,
+}
+
+impl Drop for Unit {
+    fn drop(&mut self) {
+        // Closing the connection aborts the transaction, an open transaction
+        // must not go back to the pool.
+        if let Some(connection) = self.connection.get_mut().take() {
+            drop(Object::take(connection));
+        }
+    }
+}
+
+impl PostgresUnit {
+    async fn execute(&self, statement: &str) -> Result<(), Error> {
+        let connection = self.0.connection.lock().await;
+        let connection = connection.as_ref().ok_or("unit of work is finished")?;
+        Ok(connection.batch_execute(statement).await?)
+    }
+
+    async fn finish(&self, statement: &str) -> Result<(), Error> {
+        let connection = self
+            .0
+            .connection
+            .lock()
+            .await
+            .take()
+            .ok_or("unit of work is finished")?;
+        connection.batch_execute(statement).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor for PostgresUnit {
+    fn statements<T: Table>(&self) -> Arc<Statements> {
+        self.0.postgres.statements::<T>()
+    }
+
+    async fn read(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        let connection = self.0.connection.lock().await;
+        let connection = connection.as_ref().ok_or("unit of work is finished")?;
+        let statement = connection.prepare_cached(statement).await?;
+        Ok(connection.query(&statement, params).await?)
+    }
+
+    /// Runs a statement in the transaction of the unit, attributed to the
+    /// principal that began it.
+    async fn write(
+        &self,
+        statement: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        self.read(statement, params).await
+    }
+
+    async fn savepoint(&self, name: &str) -> Result<(), Error> {
+        self.execute(&format!("SAVEPOINT {name}")).await
+    }
+
+    async fn release(&self, name: &str, undo: bool) -> Result<(), Error> {
+        if undo {
+            self.execute(&format!("ROLLBACK TO SAVEPOINT {name}"))
+                .await?;
+        }
+        self.execute(&format!("RELEASE SAVEPOINT {name}")).await
+    }
+}
+
+#[async_trait]
+impl interfaces::UnitOfWork for PostgresUnit {
+    type Error = Error;
+
+    fn broker(&self) -> Broker {
+        self.0.pending.clone()
+    }
+
+    async fn commit(&self) -> Result<(), Self::Error> {
+        self.finish("COMMIT").await?;
+        let envelopes = std::mem::take(&mut *self.0.pending.envelopes.lock().unwrap());
+        self.0.broker.publish_all(envelopes).await
+    }
+
+    async fn rollback(&self) -> Result<(), Self::Error> {
+        self.0.pending.envelopes.lock().unwrap().clear();
+        self.finish("ROLLBACK").await
+    }
+}
+
+impl<T: Table> interfaces::Repositories<T> for PostgresUnit {
+    fn repository(
+        &self,
+    ) -> Arc<dyn interfaces::Repository<T, Error = Error, Id = Uuid> + Send + Sync> {
+        Arc::new(Repository {
+            storage: Arc::new(self.clone()),
+        })
+    }
+}
+
+impl interfaces::PriceTimelines for PostgresUnit {
+    fn price_timeline(&self) -> Arc<dyn interfaces::PriceTimeline<Error = Error> + Send + Sync> {
+        Arc::new(PostgresPriceTimeline::in_unit(self.clone()))
+    }
+}
+
+/// Holds the messages sent in a unit of work until it commits.
+#[derive(Default)]
+struct Pending {
+    envelopes: Mutex<Vec<Envelope>>,
+}
+
+#[async_trait]
+impl MessageBroker<dyn Identifiable + Send + Sync> for Pending {
+    type Error = Error;
+
+    async fn send(
+        &self,
+        topic: &str,
+        action: &str,
+        message: &(dyn Identifiable + Send + Sync),
+    ) -> Result<(), Self::Error> {
+        self.publish(Envelope::new(topic, action, message)).await
+    }
+
+    async fn publish(&self, envelope: Envelope) -> Result<(), Self::Error> {
+        self.envelopes.lock().unwrap().push(envelope);
+        Ok(())
+    }
+}
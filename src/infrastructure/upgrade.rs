@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+/// Currency of the prices recorded before prices had one.
+const LEGACY_CURRENCY: &str = "USD";
+
+/// Brings a state recorded by an older version to the current shape when it
+/// is read, the recorded events and versions themselves never change. States
+/// from before entities had timestamps get `created_at` and `updated_at`,
+/// whole prices from before they had a currency become `Money`.
+pub fn upgrade(mut data: Value, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) -> Value {
+    if let Value::Object(fields) = &mut data {
+        fields
+            .entry("created_at")
+            .or_insert_with(|| json!(created_at));
+        fields
+            .entry("updated_at")
+            .or_insert_with(|| json!(updated_at));
+        if let Some(price) = fields.get_mut("price") {
+            *price = upgrade_price(price.take());
+        }
+    }
+    data
+}
+
+/// A price as `Money`, whole amounts without a currency are in
+/// `LEGACY_CURRENCY`.
+pub fn upgrade_price(price: Value) -> Value {
+    match price {
+        Value::Number(amount) => json!({"amount": amount, "currency": LEGACY_CURRENCY}),
+        price => price,
+    }
+}
@@ -0,0 +1,15 @@
+// Lets `mesgmon_derive` refer to this crate by name from inside it as well.
+extern crate self as mesgmon;
+
+mod app;
+pub mod application;
+pub mod cli;
+pub mod domain;
+pub mod handlers;
+pub mod infrastructure;
+mod runtime;
+#[cfg(test)]
+mod test_support;
+
+pub use app::{admin_routes, AppBuilder};
+pub use runtime::Runtime;
@@ -1,57 +1,37 @@
-use crate::application::{AppState, ProductService, UserService};
-use crate::handlers::{product, user};
-use crate::infrastructure::{Kafka, Postgres, Repository};
-use axum::routing::{post, put};
-use axum::Router;
+use mesgmon::cli::{self, Command};
+use mesgmon::{AppBuilder, Runtime};
 use std::error::Error;
-use std::sync::Arc;
+use std::net::SocketAddr;
 use log::info;
 
-mod application;
-mod domain;
-mod handlers;
-mod infrastructure;
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     if dotenvy::dotenv().is_err() {
         println!("Failed to load .env file, using manually specified env variables...");
     }
     env_logger::init();
-    
-    let database_uri = std::env::var("DATABASE_URI")?;
-    let kafka_brokers = std::env::var("KAFKA_BROKERS")?;
+    let command = cli::parse(std::env::args().skip(1))?;
     let hostaddr = std::env::var("HOSTADDR")?;
-    
-    let postgres = Arc::new(Postgres::new(&database_uri).await?);
-    let user_repo = Arc::new(Repository {
-        storage: postgres.clone(),
-    });
-    let product_repo = Arc::new(Repository { storage: postgres });
-    let kafka = Arc::new(Kafka::new(&kafka_brokers)?);
-    let state = Arc::new(AppState {
-        broker: kafka,
-        user_repo,
-        product_repo,
-        user_service: Arc::new(UserService),
-        product_service: Arc::new(ProductService),
-    });
-    
-    let user = Router::new()
-        .route("/users", post(user::create))
-        .route("/users/:id", put(user::update).delete(user::delete))
-        .with_state(state.clone());
-    let product = Router::new()
-        .route("/products", post(product::create))
-        .route(
-            "/products/:id",
-            put(product::update).delete(product::delete),
-        )
-        .with_state(state);
-    
-    let app = Router::merge(user, product);
+    let mut runtime = Runtime::from_env().await?;
+
+    if let Command::Replay { entities, options } = command {
+        return runtime.replay(&entities, &options).await;
+    }
+    let app = AppBuilder::from_env(&mut runtime).await?.build();
     let listener = tokio::net::TcpListener::bind(&hostaddr).await?;
     info!("Listening on: {}", hostaddr);
-    axum::serve(listener, app).await?;
-    Ok(())
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    runtime.shutdown().await
+}
+
+async fn shutdown_signal() {
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        log::error!("Failed to listen for shutdown signal: {}", err);
+    }
 }
@@ -0,0 +1,412 @@
+use crate::application::{
+    AppState, CrudService, InventoryService, OrderService, PriceService, ProductService,
+    PurgeService, ReplayOptions, ReplayService, DEFAULT_BATCH_LIMIT, DEFAULT_MAX_RESERVATION_TTL,
+};
+use crate::domain::interfaces::{Identifiable, MessageBroker};
+use crate::domain::models::{Product, User};
+use crate::infrastructure::{
+    Backpressure, ChangeCapture, EmailDomains, EventLog, EventRelay, EventStore, IdempotencyStore,
+    Kafka, LagMonitor, NullBroker, Postgres, PostgresInventory, PostgresOrderRepository,
+    PostgresPriceTimeline, PostgresUnitOfWork, ProductCatalog, Projector, QueuedBroker, ReadModel,
+    Repository, TopicInspector,
+};
+use log::info;
+use std::error;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+type Broker = Arc<dyn MessageBroker<dyn Identifiable + Send + Sync, Error = Error> + Send + Sync>;
+
+/// The database, Kafka and repositories configured by the environment, from
+/// which the binary replays entities or wires the served `AppState`.
+pub struct Runtime {
+    postgres: Arc<Postgres>,
+    kafka: Arc<Kafka>,
+    /// Where the events go when the publish queue is off.
+    sink: Broker,
+    kafka_brokers: String,
+    event_store: Option<Arc<EventStore>>,
+    users: Arc<Repository<User>>,
+    products: Arc<Repository<Product>>,
+    publisher: Option<Arc<QueuedBroker>>,
+}
+
+/// Value of the environment variable `name`, or `default` when it is unset.
+fn setting<T>(name: &str, default: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Into<Error>,
+{
+    std::env::var(name)
+        .unwrap_or(default.to_string())
+        .parse()
+        .map_err(|err: T::Err| format!("{name}: {}", err.into()).into())
+}
+
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl Runtime {
+    /// Connects to `DATABASE_URI` and `KAFKA_BROKERS`, migrates the database
+    /// and picks the storage of `STORAGE_MODE`.
+    pub async fn from_env() -> Result<Runtime, Error> {
+        let database_uri = std::env::var("DATABASE_URI")?;
+        let kafka_brokers = std::env::var("KAFKA_BROKERS")?;
+        let statistics_interval = setting("KAFKA_STATISTICS_INTERVAL_MS", "5000")?;
+
+        let postgres = Arc::new(Postgres::new(&database_uri).await?);
+        postgres.migrate().await?;
+        let event_store = match setting::<String>("STORAGE_MODE", "crud")?.as_str() {
+            "crud" => None,
+            "event-sourced" => {
+                let snapshot_every = setting("SNAPSHOT_EVERY", "50")?;
+                if snapshot_every < 1 {
+                    return Err(format!("SNAPSHOT_EVERY must be positive: {snapshot_every}").into());
+                }
+                Some(Arc::new(EventStore::new(postgres.pool(), snapshot_every)))
+            }
+            other => return Err(format!("unknown storage mode: {other}").into()),
+        };
+        let (users, products) = match &event_store {
+            Some(event_store) => (
+                Arc::new(Repository {
+                    storage: event_store.clone(),
+                }),
+                Arc::new(Repository {
+                    storage: event_store.clone(),
+                }),
+            ),
+            None => (
+                Arc::new(Repository {
+                    storage: postgres.clone(),
+                }),
+                Arc::new(Repository {
+                    storage: postgres.clone(),
+                }),
+            ),
+        };
+        let kafka = Arc::new(Kafka::new(
+            &kafka_brokers,
+            Duration::from_millis(statistics_interval),
+        )?);
+        Ok(Runtime {
+            postgres,
+            sink: kafka.clone(),
+            kafka,
+            kafka_brokers,
+            event_store,
+            users,
+            products,
+            publisher: None,
+        })
+    }
+
+    /// Publishes the current state of `entities`, `users` or `products`.
+    pub async fn replay(&self, entities: &[String], options: &ReplayOptions) -> Result<(), Error> {
+        let replay = ReplayService::new(self.kafka.clone(), self.postgres.clone());
+        for entity in entities {
+            let published = match entity.as_str() {
+                "users" => {
+                    replay
+                        .replay("user-events", self.users.clone(), options)
+                        .await?
+                }
+                _ => {
+                    replay
+                        .replay("product-events", self.products.clone(), options)
+                        .await?
+                }
+            };
+            info!(
+                "Replay of {} finished, {} events published",
+                entity, published
+            );
+        }
+        self.kafka.flush(Duration::from_secs(10))?;
+        Ok(())
+    }
+
+    /// Builds the served services and starts their background tasks, as
+    /// configured by the environment.
+    pub async fn state(&mut self) -> Result<Arc<AppState>, Error> {
+        let postgres = &self.postgres;
+        let kafka = &self.kafka;
+        let event_store = &self.event_store;
+        self.publisher = match setting::<String>("PUBLISH_MODE", "sync")?.as_str() {
+            "sync" => None,
+            "async" => Some(
+                QueuedBroker::new(
+                    kafka.clone(),
+                    setting("PUBLISH_QUEUE_CAPACITY", "1024")?,
+                    setting("PUBLISH_WORKERS", "4")?,
+                    setting::<Backpressure>("PUBLISH_BACKPRESSURE", "block")?,
+                    PathBuf::from(setting::<String>("PUBLISH_SPILL_DIR", "spill")?),
+                )
+                .await?,
+            ),
+            other => return Err(format!("unknown publish mode: {other}").into()),
+        };
+        let lag_groups = list(&setting::<String>("LAG_CONSUMER_GROUPS", "")?);
+        let lag_monitor = if lag_groups.is_empty() {
+            None
+        } else {
+            let topics: String = setting(
+                "LAG_TOPICS",
+                "user-events,product-events,inventory-events,order-events",
+            )?;
+            let monitor = Arc::new(LagMonitor::new(
+                &self.kafka_brokers,
+                lag_groups,
+                list(&topics),
+                setting("LAG_THRESHOLD", "1000")?,
+                Duration::from_secs(setting("LAG_STALL_WINDOW_SECS", "300")?),
+            ));
+            monitor.start(Duration::from_secs(setting("LAG_INTERVAL_SECS", "30")?));
+            Some(monitor)
+        };
+        let cdc_enabled = setting::<String>("CDC_ENABLED", "")? == "true";
+        if cdc_enabled && event_store.is_some() {
+            return Err(
+                "change data capture is not available in event-sourced storage mode".into(),
+            );
+        }
+        let change_capture = if cdc_enabled {
+            let slot: String = setting("CDC_SLOT", "mesgmon_cdc")?;
+            let capture = Arc::new(ChangeCapture::new(
+                postgres.pool(),
+                &slot,
+                "mesgmon_cdc",
+                setting("CDC_BATCH_SIZE", "1000")?,
+                kafka.clone(),
+            ));
+            capture
+                .start(Duration::from_millis(setting(
+                    "CDC_POLL_INTERVAL_MS",
+                    "500",
+                )?))
+                .await?;
+            Some(capture)
+        } else {
+            None
+        };
+        let publisher: Broker = match &self.publisher {
+            Some(publisher) => publisher.clone(),
+            None => self.sink.clone(),
+        };
+        // Change data capture and the event relay publish the changes of the
+        // users and products, the other streams are published as they happen.
+        let broker: Broker = match (&change_capture, event_store) {
+            (None, None) => publisher.clone(),
+            _ => Arc::new(NullBroker),
+        };
+        if event_store.is_some() {
+            let relay = Arc::new(EventRelay::new(
+                postgres.pool(),
+                kafka.clone(),
+                postgres.clone(),
+                500,
+            ));
+            relay.start(Duration::from_millis(500));
+        }
+        let (projector, read_model) = if setting::<String>("PROJECTIONS_ENABLED", "")? == "true" {
+            let log = match event_store {
+                Some(_) => EventLog::Events,
+                None => EventLog::History,
+            };
+            let projector = Arc::new(Projector::new(
+                postgres.pool(),
+                log,
+                vec![Box::new(ProductCatalog), Box::new(EmailDomains)],
+                setting("PROJECTION_BATCH_SIZE", "500")?,
+            ));
+            projector.start(Duration::from_millis(setting(
+                "PROJECTION_INTERVAL_MS",
+                "500",
+            )?));
+            (
+                Some(projector),
+                Some(Arc::new(ReadModel::new(postgres.pool()))),
+            )
+        } else {
+            (None, None)
+        };
+        let purge_interval = Duration::from_secs(setting("PURGE_INTERVAL_SECS", "3600")?);
+        let purge = Arc::new(PurgeService::new(
+            broker.clone(),
+            Duration::from_secs(setting("PURGE_RETENTION_SECS", "2592000")?),
+            500,
+        ));
+        purge.schedule("user-events", self.users.clone(), purge_interval);
+        purge.schedule("product-events", self.products.clone(), purge_interval);
+        let idempotency = Arc::new(
+            IdempotencyStore::new(
+                postgres.pool(),
+                Duration::from_secs(setting("IDEMPOTENCY_TTL_SECS", "86400")?),
+            )
+            .with_lease(Duration::from_secs(setting(
+                "IDEMPOTENCY_LEASE_SECS",
+                "60",
+            )?)),
+        );
+        idempotency.start(purge_interval);
+        let batch_limit = setting("BATCH_MAX_SIZE", &DEFAULT_BATCH_LIMIT.to_string())?;
+        // Resources whose entities PUT creates when the id does not exist.
+        let upsert = list(&setting::<String>("UPSERT_RESOURCES", "")?);
+        let mut users = CrudService::new("user-events", self.users.clone(), broker.clone())
+            .with_batch_limit(batch_limit)
+            .with_upsert(upsert.iter().any(|resource| resource == "users"));
+        let inventory = Arc::new(
+            InventoryService::new(
+                "inventory-events",
+                self.products.clone(),
+                Arc::new(PostgresInventory::new(postgres.pool())),
+                publisher.clone(),
+                Duration::from_secs(setting("RESERVATION_TTL_SECS", "900")?),
+                500,
+            )
+            .with_max_reservation_ttl(Duration::from_secs(setting(
+                "RESERVATION_MAX_TTL_SECS",
+                &DEFAULT_MAX_RESERVATION_TTL.as_secs().to_string(),
+            )?)),
+        );
+        inventory.start(Duration::from_millis(setting(
+            "RESERVATION_EXPIRY_INTERVAL_MS",
+            "1000",
+        )?));
+        // The orders reference the rows of the CRUD tables.
+        let orders = event_store.is_none().then(|| {
+            Arc::new(OrderService::new(
+                "order-events",
+                Arc::new(PostgresOrderRepository::new(postgres.pool())),
+                self.users.clone(),
+                self.products.clone(),
+                publisher.clone(),
+            ))
+        });
+        let mut products =
+            CrudService::new("product-events", self.products.clone(), broker.clone())
+                .with_batch_limit(batch_limit)
+                .with_upsert(upsert.iter().any(|resource| resource == "products"));
+        let mut prices = PriceService::new(
+            "product-events",
+            self.products.clone(),
+            Arc::new(PostgresPriceTimeline::new(postgres.pool())),
+            publisher.clone(),
+            500,
+        );
+        // Without units of work the changes run one after the other.
+        let units = event_store
+            .is_none()
+            .then(|| Arc::new(PostgresUnitOfWork::new(postgres.clone(), broker)));
+        if let Some(units) = &units {
+            users = users.with_units(units.clone());
+            products = products.with_units(units.clone());
+            // Price changes are announced whoever publishes the updates.
+            let units = PostgresUnitOfWork::new(postgres.clone(), publisher.clone());
+            prices = prices.with_units(Arc::new(units));
+        }
+        let prices = Arc::new(prices);
+        prices.start(Duration::from_millis(setting(
+            "PRICE_SCHEDULE_INTERVAL_MS",
+            "1000",
+        )?));
+        let mut product_service = ProductService::new(products, prices.clone());
+        if let Some(units) = units {
+            product_service = product_service.with_units(units);
+        }
+        let trusted_proxies = list(&setting::<String>("TRUSTED_PROXIES", "")?)
+            .iter()
+            .map(|proxy| proxy.parse())
+            .collect::<Result<Vec<IpAddr>, _>>()?
+            .into();
+        Ok(Arc::new(AppState {
+            publisher: self.publisher.clone(),
+            kafka_metrics: Some(kafka.metrics()),
+            inspector: Some(Arc::new(TopicInspector::new(&self.kafka_brokers)?)),
+            lag_monitor,
+            change_capture,
+            projector,
+            read_model,
+            idempotency: Some(idempotency),
+            prices: Some(prices),
+            inventory: Some(inventory),
+            orders,
+            admin_token: std::env::var("ADMIN_TOKEN").ok().map(Arc::from),
+            trusted_proxies,
+            ..AppState::new(Arc::new(users), Arc::new(product_service))
+        }))
+    }
+
+    /// Drains the publish queue and flushes what Kafka still holds.
+    pub async fn shutdown(self) -> Result<(), Error> {
+        if let Some(publisher) = self.publisher {
+            info!("Flushing publish queue...");
+            publisher.shutdown().await;
+        }
+        self.kafka.flush(Duration::from_secs(10))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::interfaces::{Database, PriceTimeline};
+    use crate::test_support::{postgres, product, usd, Recorder};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URI"]
+    async fn scheduled_prices_are_announced_with_change_capture() {
+        let postgres = postgres().await;
+        let slot = format!("test_{}", Uuid::new_v4().simple());
+        if std::env::var_os("KAFKA_BROKERS").is_none() {
+            std::env::set_var("KAFKA_BROKERS", "127.0.0.1:9092");
+        }
+        std::env::set_var("CDC_ENABLED", "true");
+        std::env::set_var("CDC_SLOT", &slot);
+        let recorder = Arc::new(Recorder::default());
+        let mut runtime = Runtime {
+            sink: recorder.clone(),
+            ..Runtime::from_env().await.unwrap()
+        };
+        let state = runtime.state().await.unwrap();
+        assert!(state.change_capture.is_some());
+
+        let product = postgres.add(product()).await.unwrap();
+        PostgresPriceTimeline::new(postgres.pool())
+            .schedule(product.id, &usd(300), Utc::now())
+            .await
+            .unwrap();
+        state.prices.as_ref().unwrap().activate().await.unwrap();
+        let connection = postgres.pool().get().await.unwrap();
+        // The capture polls the slot between its sleeps.
+        for _ in 0..50 {
+            let dropped = connection
+                .execute("SELECT pg_drop_replication_slot($1)", &[&slot])
+                .await;
+            if dropped.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let sent = recorder.sent.lock().unwrap();
+        let announced = (
+            "product-events".to_string(),
+            "price-changed".to_string(),
+            product.id.to_string(),
+        );
+        assert!(sent.contains(&announced));
+    }
+}
@@ -0,0 +1,98 @@
+//! Fixtures shared by the tests that run against a database, which are
+//! ignored by default: `cargo test -- --ignored` with `DATABASE_URI` set.
+
+use crate::domain::interfaces::{Identifiable, MessageBroker};
+use crate::domain::models::{Currency, Envelope, Money, Product, User};
+use crate::infrastructure::Postgres;
+use axum::async_trait;
+use chrono::Utc;
+use serde_json::Value;
+use std::error;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+type Error = Box<dyn error::Error + Send + Sync>;
+
+static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+/// Records the published messages instead of sending them.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    pub(crate) sent: Mutex<Vec<(String, String, String)>>,
+    pub(crate) changed: Mutex<Vec<Vec<String>>>,
+    pub(crate) data: Mutex<Vec<Option<Value>>>,
+}
+
+#[async_trait]
+impl MessageBroker<dyn Identifiable + Send + Sync> for Recorder {
+    type Error = Error;
+
+    async fn send(
+        &self,
+        topic: &str,
+        action: &str,
+        message: &(dyn Identifiable + Send + Sync),
+    ) -> Result<(), Self::Error> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((topic.to_string(), action.to_string(), message.id()));
+        Ok(())
+    }
+
+    async fn publish(&self, envelope: Envelope) -> Result<(), Self::Error> {
+        if !envelope.changed.is_empty() {
+            self.changed.lock().unwrap().push(envelope.changed);
+        }
+        self.data.lock().unwrap().push(envelope.data);
+        self.sent
+            .lock()
+            .unwrap()
+            .push((envelope.topic, envelope.action, envelope.entity_id));
+        Ok(())
+    }
+}
+
+/// Connects to the database named by `DATABASE_URI` and migrates it once.
+pub(crate) async fn postgres() -> Arc<Postgres> {
+    let database_uri =
+        std::env::var("DATABASE_URI").expect("DATABASE_URI must name the test database");
+    let postgres = Arc::new(Postgres::new(&database_uri).await.unwrap());
+    MIGRATED
+        .get_or_try_init(|| postgres.migrate())
+        .await
+        .unwrap();
+    postgres
+}
+
+pub(crate) fn user() -> User {
+    let id = Uuid::new_v4();
+    User {
+        id,
+        name: "test".to_string(),
+        email: format!("{id}@example.com"),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+pub(crate) fn product() -> Product {
+    Product {
+        id: Uuid::new_v4(),
+        name: "test".to_string(),
+        price: usd(100),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+pub(crate) fn usd(amount: i64) -> Money {
+    Money::new(amount.into(), Currency::Usd).unwrap()
+}
+
+pub(crate) fn is_not_found(error: &Error) -> bool {
+    matches!(error.downcast_ref::<io::Error>(), Some(error) if error.kind() == ErrorKind::NotFound)
+}